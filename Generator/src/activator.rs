@@ -1,10 +1,13 @@
 use anyhow::{Context, Result};
 use serde_json::{json, Value};
+use std::ffi::OsStr;
 use std::fs;
 use std::process::Command;
 use tracing::{debug, info, warn};
 
+use crate::backends::DeployContext;
 use crate::detector::InstalledProgram;
+use crate::hooks;
 
 /// Result of a theme activation
 #[derive(Debug)]
@@ -19,14 +22,15 @@ pub struct ThemeActivator;
 
 impl ThemeActivator {
     /// Activate theme for all detected programs
-    pub fn activate_all(programs: &[InstalledProgram]) -> Vec<ActivationResult> {
+    #[allow(dead_code)]
+    pub fn activate_all(ctx: &DeployContext, programs: &[InstalledProgram]) -> Vec<ActivationResult> {
         let mut results = Vec::new();
 
         for program in programs {
             let result = match program.name.as_str() {
-                "vencord" => Self::activate_vencord(program),
-                "spicetify" => Self::activate_spicetify(program),
-                "cava" => Self::activate_omarcava(program),
+                "vencord" => Self::activate_vencord(ctx, program),
+                "spicetify" => Self::activate_spicetify(ctx, program),
+                "cava" => Self::activate_omarcava(ctx, program),
                 _ => Ok(ActivationResult {
                     program: program.name.clone(),
                     success: false,
@@ -47,39 +51,34 @@ impl ThemeActivator {
         results
     }
 
-    /// Activate Omarcord theme (wrapper for Vencord with correct theme name)
-    pub fn activate_omarcord(program: &InstalledProgram) -> Result<ActivationResult> {
-        Self::activate_vencord_with_name(program, "omarcord.theme.css")
-    }
-
     /// Activate Omarchify theme (wrapper for Spicetify with correct scheme name)
-    pub fn activate_omarchify(program: &InstalledProgram) -> Result<ActivationResult> {
-        Self::activate_spicetify_with_scheme(program, "text", "Omarchify")
+    pub fn activate_omarchify(ctx: &DeployContext, program: &InstalledProgram) -> Result<ActivationResult> {
+        Self::activate_spicetify_with_scheme(ctx, program, "text", "Omarchify")
     }
 
     /// Activate Omarcava theme (reload running Cava instances)
-    pub fn activate_omarcava(program: &InstalledProgram) -> Result<ActivationResult> {
+    pub fn activate_omarcava(ctx: &DeployContext, program: &InstalledProgram) -> Result<ActivationResult> {
         debug!("Activating Omarcava theme...");
 
         // Check if Cava is running
-        let cava_running = Command::new("pgrep")
-            .arg("cava")
-            .output()
+        let cava_running = hooks::output_with_timeout(Command::new("pgrep").arg("cava"), ctx.command_timeout())
             .map(|o| o.status.success())
             .unwrap_or(false);
 
         if cava_running {
             // Cava requires manual reload (press 'r' key)
             // Send notification if notify-send is available
-            let notification_sent = Command::new("notify-send")
-                .args(&[
-                    "-u", "normal",
-                    "-t", "3000",
-                    "-a", "Omarchy Theme Generator",
-                    "Cava Theme Updated",
-                    "Press 'r' in Cava to reload, or restart Cava to see new theme"
-                ])
-                .output()
+            let notification_sent = ctx
+                .run(
+                    OsStr::new("notify-send"),
+                    &[
+                        "-u", "normal",
+                        "-t", "3000",
+                        "-a", "Omarchy Theme Generator",
+                        "Cava Theme Updated",
+                        "Press 'r' in Cava to reload, or restart Cava to see new theme",
+                    ],
+                )
                 .map(|o| o.status.success())
                 .unwrap_or(false);
 
@@ -106,12 +105,12 @@ impl ThemeActivator {
     }
 
     /// Activate Vencord theme by updating settings.json
-    fn activate_vencord(program: &InstalledProgram) -> Result<ActivationResult> {
-        Self::activate_vencord_with_name(program, "omarchy-theme")
+    fn activate_vencord(ctx: &DeployContext, program: &InstalledProgram) -> Result<ActivationResult> {
+        Self::activate_vencord_with_name(ctx, program, "omarchy-theme")
     }
 
     /// Activate Vencord theme with a specific theme filename
-    fn activate_vencord_with_name(program: &InstalledProgram, theme_name: &str) -> Result<ActivationResult> {
+    pub fn activate_vencord_with_name(ctx: &DeployContext, program: &InstalledProgram, theme_name: &str) -> Result<ActivationResult> {
         debug!("Activating Vencord theme: {}...", theme_name);
 
         let settings_file = program
@@ -126,20 +125,15 @@ impl ThemeActivator {
                 settings_file
             );
 
-            // Create parent directory if needed
-            if let Some(parent) = settings_file.parent() {
-                fs::create_dir_all(parent)
-                    .context("Failed to create settings directory")?;
-            }
-
             let default_settings = json!({
                 "enabledThemes": [theme_name],
                 "plugins": {}
             });
 
-            fs::write(
+            // Creates the settings directory if needed
+            ctx.write_file(
                 settings_file,
-                serde_json::to_string_pretty(&default_settings)?,
+                &serde_json::to_string_pretty(&default_settings)?,
             )
             .context("Failed to write settings file")?;
 
@@ -160,7 +154,7 @@ impl ThemeActivator {
             .context("Failed to parse Vencord settings")?;
 
         // Ensure enabledThemes array exists
-        if !settings.get("enabledThemes").is_some() {
+        if settings.get("enabledThemes").is_none() {
             settings["enabledThemes"] = json!([]);
         }
 
@@ -187,9 +181,9 @@ impl ThemeActivator {
         themes.push(json!(theme_name));
 
         // Write back to file
-        fs::write(
+        ctx.write_file(
             settings_file,
-            serde_json::to_string_pretty(&settings)?,
+            &serde_json::to_string_pretty(&settings)?,
         )
         .context("Failed to write Vencord settings")?;
 
@@ -203,12 +197,13 @@ impl ThemeActivator {
     }
 
     /// Activate Spicetify theme using CLI
-    fn activate_spicetify(program: &InstalledProgram) -> Result<ActivationResult> {
-        Self::activate_spicetify_with_scheme(program, "omarchy-theme", "default")
+    fn activate_spicetify(ctx: &DeployContext, program: &InstalledProgram) -> Result<ActivationResult> {
+        Self::activate_spicetify_with_scheme(ctx, program, "omarchy-theme", "default")
     }
 
     /// Activate Spicetify with specific theme and color scheme
     fn activate_spicetify_with_scheme(
+        ctx: &DeployContext,
         program: &InstalledProgram,
         theme_name: &str,
         color_scheme: &str,
@@ -227,14 +222,13 @@ impl ThemeActivator {
         // Get the spicetify command path
         let spicetify_cmd = program.cli_path.as_ref()
             .map(|p| p.as_os_str())
-            .unwrap_or_else(|| std::ffi::OsStr::new("spicetify"));
+            .unwrap_or_else(|| OsStr::new("spicetify"));
 
         debug!("Using spicetify command: {:?}", spicetify_cmd);
 
         // Set the theme
-        let config_theme_output = Command::new(spicetify_cmd)
-            .args(["config", "current_theme", theme_name])
-            .output()
+        let config_theme_output = ctx
+            .run(spicetify_cmd, &["config", "current_theme", theme_name])
             .context("Failed to execute spicetify config current_theme")?;
 
         if !config_theme_output.status.success() {
@@ -247,9 +241,8 @@ impl ThemeActivator {
         }
 
         // Set the color scheme
-        let config_scheme_output = Command::new(spicetify_cmd)
-            .args(["config", "color_scheme", color_scheme])
-            .output()
+        let config_scheme_output = ctx
+            .run(spicetify_cmd, &["config", "color_scheme", color_scheme])
             .context("Failed to execute spicetify config color_scheme")?;
 
         if !config_scheme_output.status.success() {
@@ -262,9 +255,8 @@ impl ThemeActivator {
         }
 
         // Apply the theme (requires Spotify restart)
-        let apply_output = Command::new(spicetify_cmd)
-            .arg("apply")
-            .output()
+        let apply_output = ctx
+            .run(spicetify_cmd, &["apply"])
             .context("Failed to execute spicetify apply command")?;
 
         if !apply_output.status.success() {
@@ -285,23 +277,8 @@ impl ThemeActivator {
         })
     }
 
-    /// Deactivate theme for a program
-    #[allow(dead_code)]
-    pub fn deactivate(program: &InstalledProgram) -> Result<ActivationResult> {
-        match program.name.as_str() {
-            "vencord" => Self::deactivate_vencord(program),
-            "spicetify" => Self::deactivate_spicetify(program),
-            _ => Ok(ActivationResult {
-                program: program.name.clone(),
-                success: false,
-                message: format!("Deactivation not supported for {}", program.name),
-            }),
-        }
-    }
-
-    /// Deactivate Vencord theme
-    #[allow(dead_code)]
-    fn deactivate_vencord(program: &InstalledProgram) -> Result<ActivationResult> {
+    /// Deactivate a Vencord theme by its filename
+    pub fn deactivate_vencord_with_name(ctx: &DeployContext, program: &InstalledProgram, theme_name: &str) -> Result<ActivationResult> {
         let settings_file = program
             .config_file
             .as_ref()
@@ -322,16 +299,15 @@ impl ThemeActivator {
             .context("Failed to parse Vencord settings")?;
 
         if let Some(themes) = settings["enabledThemes"].as_array_mut() {
-            let theme_name = "omarchy-theme";
             themes.retain(|t| t.as_str() != Some(theme_name));
 
-            fs::write(
+            ctx.write_file(
                 settings_file,
-                serde_json::to_string_pretty(&settings)?,
+                &serde_json::to_string_pretty(&settings)?,
             )
             .context("Failed to write Vencord settings")?;
 
-            info!("✓ Disabled Vencord theme");
+            info!("✓ Disabled Vencord theme: {}", theme_name);
         }
 
         Ok(ActivationResult {
@@ -342,8 +318,7 @@ impl ThemeActivator {
     }
 
     /// Deactivate Spicetify theme
-    #[allow(dead_code)]
-    fn deactivate_spicetify(program: &InstalledProgram) -> Result<ActivationResult> {
+    pub fn deactivate_spicetify(ctx: &DeployContext, program: &InstalledProgram) -> Result<ActivationResult> {
         if !program.cli_available {
             return Ok(ActivationResult {
                 program: program.name.clone(),
@@ -355,12 +330,11 @@ impl ThemeActivator {
         // Get the spicetify command path
         let spicetify_cmd = program.cli_path.as_ref()
             .map(|p| p.as_os_str())
-            .unwrap_or_else(|| std::ffi::OsStr::new("spicetify"));
+            .unwrap_or_else(|| OsStr::new("spicetify"));
 
         // Restore to default theme
-        let output = Command::new(spicetify_cmd)
-            .args(["config", "current_theme", ""])
-            .output()
+        let output = ctx
+            .run(spicetify_cmd, &["config", "current_theme", ""])
             .context("Failed to execute spicetify command")?;
 
         if !output.status.success() {
@@ -372,9 +346,7 @@ impl ThemeActivator {
             });
         }
 
-        Command::new(spicetify_cmd)
-            .arg("apply")
-            .output()
+        ctx.run(spicetify_cmd, &["apply"])
             .context("Failed to apply default theme")?;
 
         info!("✓ Reset Spicetify to default theme");
@@ -390,7 +362,8 @@ impl ThemeActivator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::config::Config;
+    use crate::dryrun::{Action, DryRun};
     use tempfile::TempDir;
 
     #[test]
@@ -408,7 +381,9 @@ mod tests {
             false,
         );
 
-        let result = ThemeActivator::activate_vencord(&program).unwrap();
+        let config = Config::default();
+        let ctx = DeployContext::new(&config, temp_dir.path());
+        let result = ThemeActivator::activate_vencord(&ctx, &program).unwrap();
         assert!(result.success);
 
         // Verify file was created with correct content
@@ -439,7 +414,9 @@ mod tests {
             false,
         );
 
-        let result = ThemeActivator::activate_vencord(&program).unwrap();
+        let config = Config::default();
+        let ctx = DeployContext::new(&config, temp_dir.path());
+        let result = ThemeActivator::activate_vencord(&ctx, &program).unwrap();
         assert!(result.success);
 
         // Verify our theme was added
@@ -449,4 +426,53 @@ mod tests {
         assert_eq!(themes.len(), 2);
         assert!(themes.contains(&json!("omarchy-theme")));
     }
+
+    #[test]
+    fn test_vencord_activation_dry_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings_file = temp_dir.path().join("settings.json");
+        let existing = serde_json::to_string_pretty(&json!({ "enabledThemes": [] })).unwrap();
+        fs::write(&settings_file, &existing).unwrap();
+
+        let program = InstalledProgram::new(
+            "vencord",
+            temp_dir.path().to_path_buf(),
+            Some(settings_file.clone()),
+            true,
+            false,
+        );
+
+        let config = Config::default();
+        let dry_run = DryRun::new();
+        let ctx = DeployContext::new(&config, temp_dir.path()).with_dry_run(Some(&dry_run));
+        assert!(ThemeActivator::activate_vencord(&ctx, &program).unwrap().success);
+
+        // Settings are untouched, the edit is recorded with a diff
+        assert_eq!(fs::read_to_string(&settings_file).unwrap(), existing);
+        let actions = dry_run.actions();
+        assert!(matches!(&actions[..], [Action::Write { path, .. }] if *path == settings_file));
+        assert!(actions[0].to_string().contains("+    \"omarchy-theme\""));
+    }
+
+    #[test]
+    fn test_vencord_deactivation_by_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings_file = temp_dir.path().join("settings.json");
+        fs::write(&settings_file, json!({ "enabledThemes": ["other.css", "omarcord.theme.css"] }).to_string()).unwrap();
+
+        let program = InstalledProgram::new(
+            "vencord",
+            temp_dir.path().to_path_buf(),
+            Some(settings_file.clone()),
+            true,
+            false,
+        );
+
+        let config = Config::default();
+        let ctx = DeployContext::new(&config, temp_dir.path());
+        assert!(ThemeActivator::deactivate_vencord_with_name(&ctx, &program, "omarcord.theme.css").unwrap().success);
+
+        let settings: Value = serde_json::from_str(&fs::read_to_string(&settings_file).unwrap()).unwrap();
+        assert_eq!(settings["enabledThemes"], json!(["other.css"]));
+    }
 }
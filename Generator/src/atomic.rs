@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes temp files of concurrent writes within this process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace a file so readers see either the old or the new content, never a
+/// truncated mix: write a temp file in the same directory, fsync it, then
+/// rename it over the target. The target's permissions are kept, and writes
+/// go through symlinks to the file they point at, like `fs::write`.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = if path.is_symlink() {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+
+    let dir = parent_dir(&path);
+    let temp_path = temp_path(&path)?;
+
+    let result = write_and_rename(&path, &temp_path, contents.as_ref());
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result.with_context(|| format!("Failed to write {:?}", path))?;
+
+    // Persist the rename itself
+    if let Ok(dir) = File::open(dir) {
+        dir.sync_all().ok();
+    }
+
+    Ok(())
+}
+
+/// Replace whatever is at `link` with a symlink to `source` in one rename
+pub fn symlink_atomic(source: &Path, link: &Path) -> Result<()> {
+    let temp_path = temp_path(link)?;
+
+    std::os::unix::fs::symlink(source, &temp_path)
+        .with_context(|| format!("Failed to create symlink: {:?} -> {:?}", link, source))?;
+
+    if let Err(e) = fs::rename(&temp_path, link) {
+        fs::remove_file(&temp_path).ok();
+        return Err(e).with_context(|| format!("Failed to replace {:?}", link));
+    }
+
+    Ok(())
+}
+
+fn write_and_rename(path: &Path, temp_path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+
+    file.write_all(contents)?;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+
+    file.sync_all()?;
+    fs::rename(temp_path, path)?;
+
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Hidden sibling of `path` for staging a replacement
+fn temp_path(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid file path: {:?}", path))?
+        .to_string_lossy();
+
+    Ok(parent_dir(path).join(format!(
+        ".{}.tmp-{}-{}",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_preserves_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("omarclock");
+
+        write_atomic(&path, "#!/bin/bash\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        write_atomic(&path, "#!/bin/bash\necho new\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "#!/bin/bash\necho new\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+
+        // No temp files are left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_follows_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let real = temp_dir.path().join("dotfiles-config");
+        let link = temp_dir.path().join("config");
+        fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        write_atomic(&link, "new").unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_symlink_atomic_replaces_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("color.ini");
+        let link = temp_dir.path().join("link.ini");
+        fs::write(&source, "[Omarchify]").unwrap();
+        fs::write(&link, "old file").unwrap();
+
+        symlink_atomic(&source, &link).unwrap();
+
+        assert_eq!(fs::read_link(&link).unwrap(), source);
+        assert!(write_atomic(&temp_dir.path().join("missing/dir/file"), "x").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! Generic file: any program themed by a single rendered file, configured
+//! entirely from `[[programs]]` with `type = "file"`
+
+use super::{rendered, DeployContext, ProgramBackend, RenderedFile};
+use crate::config::{expand_path, ProgramConfig};
+use crate::detector::{InstalledProgram, ProgramDetector};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tracing::{debug, info, warn};
+
+pub struct FileBackend;
+
+/// Expanded `target` of a file program
+fn target_path(program: &ProgramConfig) -> Result<PathBuf> {
+    let target = program
+        .target
+        .as_deref()
+        .with_context(|| format!("{} has type = \"file\" but no target", program.name))?;
+
+    expand_path(target)
+}
+
+impl ProgramBackend for FileBackend {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn detect(&self, program: &ProgramConfig) -> Option<InstalledProgram> {
+        let target = match target_path(program) {
+            Ok(target) => target,
+            Err(e) => {
+                warn!("{:#}", e);
+                return None;
+            }
+        };
+
+        let mut cli_path = None;
+        if let Some(rule) = &program.detect {
+            if let Some(binary) = &rule.binary {
+                cli_path = ProgramDetector::find_in_path(binary);
+                if cli_path.is_none() {
+                    debug!("{}: {} not found in PATH", program.name, binary);
+                    return None;
+                }
+            }
+
+            if let Some(path) = &rule.path {
+                if !expand_path(path).is_ok_and(|p| p.exists()) {
+                    debug!("{}: {} doesn't exist", program.name, path);
+                    return None;
+                }
+            }
+        }
+
+        let target_dir = target.parent().map(PathBuf::from).unwrap_or_default();
+        Some(
+            InstalledProgram::new(program.name.clone(), target_dir, Some(target), true, cli_path.is_some())
+                .with_cli_path(cli_path),
+        )
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        let target = installed.config_file.as_ref().context("Target path not set")?;
+        let mode = program.file_mode()?;
+
+        if program.backup.enabled(ctx.config.create_backups) {
+            ctx.store_backup(target, &program.name)?;
+        }
+
+        ctx.write_file(target, rendered(files, &program.output_file)?)?;
+        if let Some(mode) = mode {
+            ctx.set_mode(target, mode)?;
+        }
+        info!("✓ Wrote {} to: {:?}", program.name, target);
+
+        Ok(())
+    }
+
+    fn targets(&self, _program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        installed.config_file.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::BackupStore;
+    use crate::config::{BackupPolicy, BackupSettings, Config, DetectRule};
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn file_program(target: &std::path::Path) -> ProgramConfig {
+        ProgramConfig {
+            name: "my-program".to_string(),
+            kind: Some("file".to_string()),
+            enabled: true,
+            output_file: "my-program.conf".to_string(),
+            template: "my-program".to_string(),
+            target: Some(target.display().to_string()),
+            mode: Some("0600".to_string()),
+            backup: BackupPolicy::Never,
+            ..ProgramConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_detect_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut program = file_program(&temp_dir.path().join("out.conf"));
+
+        // No rule: always deployable
+        let installed = FileBackend.detect(&program).unwrap();
+        assert_eq!(installed.config_file, Some(temp_dir.path().join("out.conf")));
+
+        program.detect = Some(DetectRule {
+            binary: Some("ls".to_string()),
+            path: None,
+        });
+        assert!(FileBackend.detect(&program).is_some());
+
+        program.detect = Some(DetectRule {
+            binary: None,
+            path: Some(temp_dir.path().join("missing").display().to_string()),
+        });
+        assert!(FileBackend.detect(&program).is_none());
+
+        program.target = None;
+        program.detect = None;
+        assert!(FileBackend.detect(&program).is_none());
+    }
+
+    #[test]
+    fn test_deploy_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("app/colors.conf");
+        let program = file_program(&target);
+        let config = Config {
+            generated_themes_dir: temp_dir.path().join("generated"),
+            backups: BackupSettings {
+                dir: temp_dir.path().join("backups"),
+                ..BackupSettings::default()
+            },
+            ..Config::default()
+        };
+        let ctx = DeployContext::new(&config, temp_dir.path());
+
+        let installed = FileBackend.detect(&program).unwrap();
+        let files = vec![RenderedFile {
+            file_name: "my-program.conf".to_string(),
+            content: "color = red\n".to_string(),
+        }];
+        FileBackend.deploy(&ctx, &program, &installed, &files).unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "color = red\n");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(FileBackend.verify(&program, &installed).ok);
+
+        // Redeploying with backups on keeps the replaced file
+        let backed_up = ProgramConfig {
+            backup: BackupPolicy::Always,
+            ..file_program(&target)
+        };
+        FileBackend.deploy(&ctx, &backed_up, &installed, &files).unwrap();
+
+        // The replaced file went into the backup store
+        let backups = BackupStore::new(&config.backups.dir).entries().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].source, target);
+    }
+}
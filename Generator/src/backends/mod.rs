@@ -0,0 +1,553 @@
+//! Program backends
+//!
+//! Every themed program is a `ProgramBackend`: it knows how to find the
+//! program, render its files, put them in place and make the program pick
+//! them up. Backends are looked up in a `BackendRegistry` by the
+//! `[[programs]]` name, so a new target is one module plus one `register` call.
+
+mod file;
+mod omarcava;
+mod omarchify;
+mod omarclock;
+mod omarcord;
+mod omarvscode;
+
+use crate::activator::ActivationResult;
+use crate::atomic;
+use crate::backup::BackupStore;
+use crate::color::ColorPalette;
+use crate::config::{Config, ProgramConfig};
+use crate::detector::InstalledProgram;
+use crate::dryrun::{Action, DryRun};
+use crate::hooks::{self, HookResult};
+use crate::templates::{Provenance, TemplateRenderer};
+use crate::transaction::Journal;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ffi::OsStr;
+use std::process::{Command, ExitStatus, Output};
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub use file::FileBackend;
+pub use omarcava::OmarcavaBackend;
+pub use omarchify::OmarchifyBackend;
+pub use omarclock::OmarclockBackend;
+pub use omarcord::OmarcordBackend;
+pub use omarvscode::OmarvscodeBackend;
+
+/// A file produced by rendering a program's templates
+#[derive(Debug, Clone)]
+pub struct RenderedFile {
+    /// File name inside `generated_themes_dir`
+    pub file_name: String,
+    pub content: String,
+}
+
+/// Result of checking a program's deployed theme
+#[derive(Debug)]
+pub struct Verification {
+    pub ok: bool,
+    pub message: String,
+}
+
+/// Everything a backend needs to render its templates
+pub struct RenderContext<'a> {
+    pub renderer: &'a TemplateRenderer,
+    pub palette: &'a ColorPalette,
+    pub provenance: &'a Provenance,
+}
+
+impl RenderContext<'_> {
+    /// Render a template with the program's variables
+    pub fn render(&self, template: &str, program: &ProgramConfig) -> Result<String> {
+        self.renderer
+            .render_with_provenance(template, self.palette, &program.variables, self.provenance)
+            .with_context(|| format!("Failed to render {} template for {}", template, program.name))
+    }
+}
+
+/// Configuration and file operations shared by all backends during a deploy
+/// With a dry run attached, every operation is recorded instead of performed;
+/// with a journal attached, the previous state of every touched path is kept
+/// so a failed deploy can be rolled back.
+pub struct DeployContext<'a> {
+    pub config: &'a Config,
+    pub theme_dir: &'a Path,
+    pub dry_run: Option<&'a DryRun>,
+    pub journal: Option<&'a Journal>,
+    /// Program being deployed, used to group its backups
+    pub program: Option<&'a str>,
+}
+
+impl<'a> DeployContext<'a> {
+    pub fn new(config: &'a Config, theme_dir: &'a Path) -> Self {
+        DeployContext {
+            config,
+            theme_dir,
+            dry_run: None,
+            journal: None,
+            program: None,
+        }
+    }
+
+    /// Record operations in `dry_run` instead of touching disk
+    pub fn with_dry_run(mut self, dry_run: Option<&'a DryRun>) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Attribute backups to `program`
+    pub fn with_program(mut self, program: &'a str) -> Self {
+        self.program = Some(program);
+        self
+    }
+
+    /// Record the previous state of touched paths in `journal`
+    pub fn with_journal(mut self, journal: &'a Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Save a rendered file to the centralized generated themes directory
+    pub fn write_generated(&self, file: &RenderedFile) -> Result<PathBuf> {
+        let path = self.config.generated_themes_dir.join(&file.file_name);
+        self.write_file(&path, &file.content)?;
+        Ok(path)
+    }
+
+    /// Atomically write a file, creating its parent directory if needed
+    pub fn write_file(&self, path: &Path, content: &str) -> Result<()> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    /// Write raw bytes, such as a backup that may not be UTF-8
+    pub fn write_bytes(&self, path: &Path, content: &[u8]) -> Result<()> {
+        if let Some(dry_run) = self.dry_run {
+            dry_run.record(Action::write(path.to_path_buf(), &String::from_utf8_lossy(content)));
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+        }
+
+        if let Some(journal) = self.journal {
+            journal.record_file(path);
+        }
+
+        atomic::write_atomic(path, content)
+    }
+
+    /// Delete a file or symlink; a missing file is not an error
+    pub fn remove_file(&self, path: &Path) -> Result<()> {
+        if !path.exists() && !path.is_symlink() {
+            return Ok(());
+        }
+
+        if let Some(dry_run) = self.dry_run {
+            dry_run.record(Action::Remove {
+                path: path.to_path_buf(),
+            });
+            return Ok(());
+        }
+
+        if let Some(journal) = self.journal {
+            journal.record_link(path);
+        }
+
+        fs::remove_file(path).with_context(|| format!("Failed to remove {:?}", path))
+    }
+
+    /// Whether `path` is a symlink to a generated file, as a deploy leaves it
+    pub fn is_generated_link(&self, path: &Path) -> bool {
+        fs::read_link(path).is_ok_and(|target| target.starts_with(&self.config.generated_themes_dir))
+    }
+
+    /// Remove the symlinks among `paths` that point into `generated_themes_dir`
+    /// Returns how many were removed; files of the user's own are left alone.
+    pub fn remove_generated_links(&self, paths: &[PathBuf]) -> Result<usize> {
+        let mut removed = 0;
+        for path in paths {
+            if self.is_generated_link(path) {
+                self.remove_file(path)?;
+                info!("✓ Removed symlink: {:?}", path);
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Copy an existing file to the backup directory if backups are enabled
+    pub fn backup_file(&self, path: &Path, backup_name: &str) -> Result<Option<PathBuf>> {
+        if !self.config.create_backups {
+            return Ok(None);
+        }
+
+        self.store_backup(path, backup_name)
+    }
+
+    /// Copy an existing file to the backup store
+    pub fn store_backup(&self, path: &Path, backup_name: &str) -> Result<Option<PathBuf>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let store = BackupStore::new(&self.config.backups.dir);
+
+        if let Some(dry_run) = self.dry_run {
+            let content = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+            let backup = store.object_path(&content);
+            dry_run.record(Action::Backup {
+                path: path.to_path_buf(),
+                backup: backup.clone(),
+            });
+            return Ok(Some(backup));
+        }
+
+        let program = self.program.unwrap_or(backup_name);
+        let backup_file = match store.store(program, backup_name, path) {
+            Ok(backup_file) => backup_file,
+            Err(e) => {
+                warn!("Failed to back up {:?}: {:#}", path, e);
+                return Ok(None);
+            }
+        };
+
+        if let Some(journal) = self.journal {
+            journal.record_backup(path, &backup_file);
+        }
+
+        info!("✓ Backed up {:?} to {:?}", path, backup_file);
+        Ok(Some(backup_file))
+    }
+
+    /// Set the permission bits of a file
+    pub fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        if let Some(dry_run) = self.dry_run {
+            dry_run.record(Action::SetMode {
+                path: path.to_path_buf(),
+                mode,
+            });
+            return Ok(());
+        }
+
+        if let Some(journal) = self.journal {
+            journal.record_file(path);
+        }
+
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions on {:?}", path))
+    }
+
+    /// How long a hook or command may run before it is killed
+    pub fn command_timeout(&self) -> Duration {
+        Duration::from_secs(self.config.hook_timeout_secs)
+    }
+
+    /// Run a program, capturing its output, and kill it after `command_timeout`
+    /// In a dry run nothing is executed and a successful, empty output is returned.
+    pub fn run(&self, program: &OsStr, args: &[&str]) -> Result<Output> {
+        if let Some(dry_run) = self.dry_run {
+            let mut command = program.to_string_lossy().into_owned();
+            for arg in args {
+                command.push(' ');
+                command.push_str(arg);
+            }
+            dry_run.record(Action::Command { command });
+
+            use std::os::unix::process::ExitStatusExt;
+            return Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            });
+        }
+
+        let mut command = Command::new(program);
+        command.args(args).current_dir(self.theme_dir);
+        hooks::output_with_timeout(&mut command, self.command_timeout())
+            .with_context(|| format!("Failed to run {:?}", program))
+    }
+
+    /// Run a deploy hook with `env`, in the theme directory
+    /// In a dry run the command is only recorded and reported as successful.
+    pub fn run_hook(&self, name: &str, command: &str, env: &[(String, String)]) -> HookResult {
+        if let Some(dry_run) = self.dry_run {
+            dry_run.record(Action::Command {
+                command: command.to_string(),
+            });
+            return HookResult {
+                name: name.to_string(),
+                command: command.to_string(),
+                success: true,
+                message: "dry run".to_string(),
+                output: String::new(),
+            };
+        }
+
+        hooks::run_hook(name, command, env, self.theme_dir, self.command_timeout())
+    }
+
+    /// Point `link` at `source`, replacing whatever is there
+    pub fn symlink(&self, source: &Path, link: &Path) -> Result<()> {
+        if let Some(dry_run) = self.dry_run {
+            dry_run.record(Action::Symlink {
+                link: link.to_path_buf(),
+                source: source.to_path_buf(),
+            });
+            return Ok(());
+        }
+
+        if let Some(parent) = link.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if let Some(journal) = self.journal {
+            journal.record_link(link);
+        }
+
+        atomic::symlink_atomic(source, link)
+    }
+}
+
+/// Content of a rendered file by name
+fn rendered<'a>(files: &'a [RenderedFile], file_name: &str) -> Result<&'a str> {
+    files
+        .iter()
+        .find(|f| f.file_name == file_name)
+        .map(|f| f.content.as_str())
+        .with_context(|| format!("{} was not rendered", file_name))
+}
+
+/// A program that can be themed
+pub trait ProgramBackend: Send + Sync {
+    /// Name used in `[[programs]]` entries
+    fn name(&self) -> &str;
+
+    /// Find the program's installation, `None` if it isn't installed
+    fn detect(&self, program: &ProgramConfig) -> Option<InstalledProgram>;
+
+    /// Templates rendered for this program
+    fn templates(&self, program: &ProgramConfig) -> Vec<String> {
+        vec![program.template.clone()]
+    }
+
+    /// Render the program's files, main output first
+    fn render(&self, ctx: &RenderContext, program: &ProgramConfig) -> Result<Vec<RenderedFile>> {
+        Ok(vec![RenderedFile {
+            file_name: program.output_file.clone(),
+            content: ctx.render(&program.template, program)?,
+        }])
+    }
+
+    /// Put the rendered files where the program loads them
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()>;
+
+    /// Make the running program pick up the new theme
+    fn activate(
+        &self,
+        _ctx: &DeployContext,
+        _program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        Ok(ActivationResult {
+            program: installed.name.clone(),
+            success: true,
+            message: "Nothing to activate".to_string(),
+        })
+    }
+
+    /// Switch the program back to its own theme
+    /// By default this removes the links a deploy put at the targets.
+    fn deactivate(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        let removed = ctx.remove_generated_links(&self.targets(program, installed))?;
+        Ok(ActivationResult {
+            program: installed.name.clone(),
+            success: true,
+            message: if removed == 0 {
+                "No symlinks to remove".to_string()
+            } else {
+                format!("Removed {} symlinks", removed)
+            },
+        })
+    }
+
+    /// Files the deployment writes into the program's own directories
+    fn targets(&self, program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf>;
+
+    /// Check that the deployed theme is in place
+    fn verify(&self, program: &ProgramConfig, installed: &InstalledProgram) -> Verification {
+        let missing: Vec<String> = self
+            .targets(program, installed)
+            .iter()
+            .filter(|path| !path.exists())
+            .map(|path| path.display().to_string())
+            .collect();
+
+        if missing.is_empty() {
+            Verification {
+                ok: true,
+                message: "Deployed".to_string(),
+            }
+        } else {
+            Verification {
+                ok: false,
+                message: format!("Missing: {}", missing.join(", ")),
+            }
+        }
+    }
+}
+
+/// Backends by program name
+pub struct BackendRegistry {
+    backends: Vec<Box<dyn ProgramBackend>>,
+}
+
+impl BackendRegistry {
+    /// Registry with all built-in backends
+    pub fn new() -> Self {
+        let mut registry = BackendRegistry { backends: Vec::new() };
+        registry.register(Box::new(OmarcordBackend));
+        registry.register(Box::new(OmarchifyBackend));
+        registry.register(Box::new(OmarcavaBackend));
+        registry.register(Box::new(OmarclockBackend));
+        registry.register(Box::new(OmarvscodeBackend));
+        registry.register(Box::new(FileBackend));
+        registry
+    }
+
+    /// Add a backend, replacing any with the same name
+    pub fn register(&mut self, backend: Box<dyn ProgramBackend>) {
+        self.backends.retain(|b| b.name() != backend.name());
+        self.backends.push(backend);
+    }
+
+    /// Backend that handles a configured program, by its `type` or name
+    pub fn for_program(&self, program: &ProgramConfig) -> Option<&dyn ProgramBackend> {
+        self.backends
+            .iter()
+            .find(|b| b.name() == program.backend_name())
+            .map(|b| b.as_ref())
+    }
+
+    /// Names of all registered backends
+    pub fn names(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.name()).collect()
+    }
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn program(name: &str, output_file: &str) -> ProgramConfig {
+        ProgramConfig {
+            name: name.to_string(),
+            enabled: true,
+            output_file: output_file.to_string(),
+            template: name.to_string(),
+            ..ProgramConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = BackendRegistry::new();
+
+        for name in ["omarcord", "omarchify", "omarcava", "omarclock", "omarvscode"] {
+            let backend = registry.for_program(&program(name, "out")).unwrap();
+            assert_eq!(backend.name(), name);
+        }
+        assert!(registry.for_program(&program("my-program", "out")).is_none());
+        assert_eq!(registry.names().len(), 6);
+
+        let generic = ProgramConfig {
+            kind: Some("file".to_string()),
+            ..program("my-program", "out")
+        };
+        assert_eq!(registry.for_program(&generic).unwrap().name(), "file");
+    }
+
+    #[test]
+    fn test_deploy_to_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config {
+            generated_themes_dir: temp_dir.path().join("generated"),
+            create_backups: false,
+            ..Config::default()
+        };
+        let ctx = DeployContext::new(&config, temp_dir.path());
+
+        let cava = program("omarcava", "config");
+        let installed = InstalledProgram::new(
+            "cava",
+            temp_dir.path().join("cava"),
+            Some(temp_dir.path().join("cava/config")),
+            true,
+            true,
+        );
+        let files = vec![RenderedFile {
+            file_name: "config".to_string(),
+            content: "[color]\n".to_string(),
+        }];
+
+        let backend = OmarcavaBackend;
+        assert!(!backend.verify(&cava, &installed).ok);
+
+        ctx.write_generated(&files[0]).unwrap();
+        backend.deploy(&ctx, &cava, &installed, &files).unwrap();
+
+        assert!(temp_dir.path().join("generated/config").exists());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("cava/config")).unwrap(), "[color]\n");
+        assert!(backend.verify(&cava, &installed).ok);
+    }
+
+    #[test]
+    fn test_deactivate_removes_generated_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config {
+            generated_themes_dir: temp_dir.path().join("generated"),
+            ..Config::default()
+        };
+        let ctx = DeployContext::new(&config, temp_dir.path());
+
+        let generated = temp_dir.path().join("generated/config");
+        let linked = temp_dir.path().join("cava/config");
+        let own = temp_dir.path().join("cava/own");
+        fs::create_dir_all(generated.parent().unwrap()).unwrap();
+        fs::create_dir_all(linked.parent().unwrap()).unwrap();
+        fs::write(&generated, "[color]\n").unwrap();
+        fs::write(&own, "mine").unwrap();
+        std::os::unix::fs::symlink(&generated, &linked).unwrap();
+
+        assert_eq!(ctx.remove_generated_links(&[linked.clone(), own.clone()]).unwrap(), 1);
+        assert!(!linked.is_symlink());
+        assert!(own.exists());
+
+        let installed = InstalledProgram::new("cava", temp_dir.path().join("cava"), Some(linked), true, true);
+        let result = OmarcavaBackend.deactivate(&ctx, &program("omarcava", "config"), &installed).unwrap();
+        assert_eq!(result.message, "No symlinks to remove");
+    }
+}
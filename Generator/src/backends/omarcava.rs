@@ -0,0 +1,54 @@
+//! Omarcava: Cava audio visualizer config
+
+use super::{rendered, DeployContext, ProgramBackend, RenderedFile};
+use crate::activator::{ActivationResult, ThemeActivator};
+use crate::config::ProgramConfig;
+use crate::detector::{InstalledProgram, ProgramDetector};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tracing::info;
+
+pub struct OmarcavaBackend;
+
+impl ProgramBackend for OmarcavaBackend {
+    fn name(&self) -> &str {
+        "omarcava"
+    }
+
+    fn detect(&self, _program: &ProgramConfig) -> Option<InstalledProgram> {
+        ProgramDetector::detect_cava()
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        let config_file = installed
+            .config_file
+            .as_ref()
+            .context("Cava config file path not set")?;
+
+        ctx.backup_file(config_file, "cava_config")?;
+        ctx.write_file(config_file, rendered(files, &program.output_file)?)?;
+        info!("✓ Wrote Omarcava config to: {:?}", config_file);
+
+        Ok(())
+    }
+
+    fn activate(
+        &self,
+        ctx: &DeployContext,
+        _program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        // Sends a reload notification to running instances
+        ThemeActivator::activate_omarcava(ctx, installed)
+    }
+
+    fn targets(&self, _program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        installed.config_file.iter().cloned().collect()
+    }
+}
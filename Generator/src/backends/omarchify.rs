@@ -0,0 +1,231 @@
+//! Omarchify: Spotify color scheme for the Spicetify text theme
+
+use super::{rendered, DeployContext, ProgramBackend, RenderContext, RenderedFile};
+use crate::activator::{ActivationResult, ThemeActivator};
+use crate::config::ProgramConfig;
+use crate::detector::{InstalledProgram, ProgramDetector};
+use crate::templates::{OutputFormat, Provenance};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Color schemes of the Omarchify text theme, kept next to the generated one
+/// A copy of `Omarchify/text/color.ini`, so the crate builds on its own.
+const BASE_COLOR_INI: &str = include_str!("../../assets/omarchify-color.ini");
+
+/// Section of color.ini holding the generated scheme
+const SECTION: &str = "Omarchify";
+
+pub struct OmarchifyBackend;
+
+/// Split an ini file into the text before the first section and `(name, text)`
+/// per section, each section's text running up to the next header
+fn split_sections(content: &str) -> (&str, Vec<(&str, &str)>) {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let name = line.trim().strip_prefix('[').and_then(|rest| rest.strip_suffix(']'));
+        if let Some(name) = name {
+            starts.push((offset, name));
+        }
+        offset += line.len();
+    }
+
+    let preamble = &content[..starts.first().map_or(content.len(), |(start, _)| *start)];
+    let sections = starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, name))| {
+            let end = starts.get(i + 1).map_or(content.len(), |(next, _)| *next);
+            (*name, &content[*start..end])
+        })
+        .collect();
+
+    (preamble, sections)
+}
+
+/// Put the `section` of `update` into `existing`, replacing a section of the
+/// same name where it is or appending it
+///
+/// The provenance header heading `update` goes to the top, in place of the
+/// one a previous merge left there. The rest of `update`'s preamble is left
+/// out so the base scheme's notes aren't added again on every merge.
+fn merge_section(existing: &str, update: &str, section: &str) -> String {
+    let header = &update[..Provenance::header_len(update, OutputFormat::Ini)];
+    let (_, update_sections) = split_sections(update);
+    let new_section = update_sections
+        .iter()
+        .find(|(name, _)| *name == section)
+        .map_or("", |(_, text)| *text);
+
+    let existing = &existing[Provenance::header_len(existing, OutputFormat::Ini)..];
+
+    let (preamble, sections) = split_sections(existing);
+    let mut merged = format!("{}{}", header, preamble);
+    let mut replaced = false;
+    for (name, text) in sections {
+        if name != section {
+            merged.push_str(text);
+        } else if !replaced {
+            merged.push_str(new_section.trim_end());
+            merged.push_str("\n\n");
+            replaced = true;
+        }
+    }
+
+    if !replaced {
+        let end = merged.trim_end().len();
+        merged.truncate(end);
+        merged.push_str("\n\n");
+        merged.push_str(new_section);
+    }
+
+    let end = merged.trim_end().len();
+    merged.truncate(end);
+    merged.push('\n');
+    merged
+}
+
+impl ProgramBackend for OmarchifyBackend {
+    fn name(&self) -> &str {
+        "omarchify"
+    }
+
+    fn detect(&self, _program: &ProgramConfig) -> Option<InstalledProgram> {
+        ProgramDetector::detect_spicetify()
+    }
+
+    fn render(&self, ctx: &RenderContext, program: &ProgramConfig) -> Result<Vec<RenderedFile>> {
+        let omarchify_section = ctx.render(&program.template, program)?;
+
+        Ok(vec![RenderedFile {
+            file_name: program.output_file.clone(),
+            content: merge_section(BASE_COLOR_INI, &omarchify_section, SECTION),
+        }])
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        let content = rendered(files, &program.output_file)?;
+        let color_ini = installed.theme_dir.join("text/color.ini");
+
+        // A color.ini of the user's own keeps its other schemes
+        if color_ini.is_file() && !color_ini.is_symlink() {
+            let existing =
+                fs::read_to_string(&color_ini).with_context(|| format!("Failed to read {:?}", color_ini))?;
+            ctx.backup_file(&color_ini, "omarchify_color.ini")?;
+            ctx.write_file(&color_ini, &merge_section(&existing, content, SECTION))?;
+            info!("✓ Merged [{}] into: {:?}", SECTION, color_ini);
+        } else if ctx.config.auto_symlink {
+            let generated = ctx.config.generated_themes_dir.join(&program.output_file);
+            ctx.symlink(&generated, &color_ini)?;
+            info!("✓ Symlinked Omarchify: {:?} -> {:?}", color_ini, generated);
+        } else {
+            ctx.write_file(&color_ini, content)?;
+            info!("✓ Wrote Omarchify color.ini to: {:?}", color_ini);
+        }
+
+        Ok(())
+    }
+
+    fn activate(
+        &self,
+        ctx: &DeployContext,
+        _program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        ThemeActivator::activate_omarchify(ctx, installed)
+    }
+
+    fn deactivate(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        let result = ThemeActivator::deactivate_spicetify(ctx, installed)?;
+        ctx.remove_generated_links(&self.targets(program, installed))?;
+        Ok(result)
+    }
+
+    fn targets(&self, _program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        vec![installed.theme_dir.join("text/color.ini")]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_replaces_section() {
+        let update = "; Generated by omarchy-theme-gen 0.1.0\n; Rendered: now\n[Omarchify]\ntext = ffffff\n";
+        let existing = "; my schemes\n\n[Nord]\ntext = eceff4\n\n[Omarchify]\ntext = 000000\n\n[Dracula]\ntext = f8f8f2\n";
+
+        let merged = merge_section(existing, update, SECTION);
+        assert_eq!(
+            merged,
+            "; Generated by omarchy-theme-gen 0.1.0\n; Rendered: now\n; my schemes\n\n[Nord]\ntext = eceff4\n\n\
+             [Omarchify]\ntext = ffffff\n\n[Dracula]\ntext = f8f8f2\n"
+        );
+
+        // Merging again swaps the header and section instead of stacking them
+        let newer = update.replace("0.1.0", "0.2.0").replace("ffffff", "eeeeee");
+        let again = merge_section(&merged, &newer, SECTION);
+        assert_eq!(again.matches("[Omarchify]").count(), 1);
+        assert_eq!(again.matches("Generated by").count(), 1);
+        assert!(again.contains("text = eeeeee"));
+    }
+
+    #[test]
+    fn test_merge_replaces_longer_header() {
+        let existing = "; Generated by omarchy-theme-gen 0.1.0\n; Theme: nord\n; Source: /themes/nord/alacritty.toml\n\
+                        ; Palette hash: 0123456789abcdef\n; Rendered: then\n; my schemes\n\n[Omarchify]\ntext = 000000\n";
+        let update = "; Generated by omarchy-theme-gen 0.2.0\n; Rendered: now\n[Omarchify]\ntext = ffffff\n";
+
+        let merged = merge_section(existing, update, SECTION);
+        assert_eq!(
+            merged,
+            "; Generated by omarchy-theme-gen 0.2.0\n; Rendered: now\n; my schemes\n\n[Omarchify]\ntext = ffffff\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_is_stable() {
+        let update = merge_section(
+            BASE_COLOR_INI,
+            "; Generated by omarchy-theme-gen 0.1.0\n; Rendered: now\n\n[Omarchify]\ntext = ffffff\n",
+            SECTION,
+        );
+        let existing = "; my schemes\n\n[Nord]\ntext = eceff4\n";
+
+        let merged = merge_section(existing, &update, SECTION);
+        assert_eq!(merge_section(&merged, &update, SECTION), merged);
+        assert!(!merged.contains("; note:"));
+    }
+
+    #[test]
+    fn test_base_matches_omarchify_theme() {
+        // Only checkable from a repository checkout
+        let theme = concat!(env!("CARGO_MANIFEST_DIR"), "/../Omarchify/text/color.ini");
+        if let Ok(upstream) = fs::read_to_string(theme) {
+            assert_eq!(BASE_COLOR_INI, upstream, "assets/omarchify-color.ini is out of date");
+        }
+    }
+
+    #[test]
+    fn test_merge_appends_to_base() {
+        let merged = merge_section(BASE_COLOR_INI, "[Omarchify]\ntext = ffffff\n", SECTION);
+        let (_, sections) = split_sections(&merged);
+
+        assert!(sections.len() > 2);
+        assert_eq!(sections.last().unwrap().0, SECTION);
+        assert!(merged.starts_with(BASE_COLOR_INI.trim_end()));
+    }
+}
@@ -0,0 +1,46 @@
+//! Omarclock: themed wrapper script for tclock
+
+use super::{rendered, DeployContext, ProgramBackend, RenderedFile};
+use crate::config::ProgramConfig;
+use crate::detector::{InstalledProgram, ProgramDetector};
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use tracing::info;
+
+pub struct OmarclockBackend;
+
+impl ProgramBackend for OmarclockBackend {
+    fn name(&self) -> &str {
+        "omarclock"
+    }
+
+    fn detect(&self, _program: &ProgramConfig) -> Option<InstalledProgram> {
+        ProgramDetector::detect_tclock()
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        // The wrapper lives at ~/.local/bin/omarclock
+        let wrapper_file = installed
+            .config_file
+            .as_ref()
+            .context("Omarclock wrapper path not set")?;
+
+        ctx.backup_file(wrapper_file, "omarclock")?;
+        ctx.write_file(wrapper_file, rendered(files, &program.output_file)?)?;
+        ctx.set_mode(wrapper_file, 0o755)?;
+        info!("✓ Wrote Omarclock wrapper to: {:?}", wrapper_file);
+        info!("✓ Omarclock ready! Run 'omarclock' to launch futuristic clock");
+
+        Ok(())
+    }
+
+    fn targets(&self, _program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        installed.config_file.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,92 @@
+//! Omarcord: Discord theme for Vencord
+
+use super::{rendered, DeployContext, ProgramBackend, RenderedFile, Verification};
+use crate::activator::{ActivationResult, ThemeActivator};
+use crate::config::ProgramConfig;
+use crate::detector::{InstalledProgram, ProgramDetector};
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+pub struct OmarcordBackend;
+
+impl ProgramBackend for OmarcordBackend {
+    fn name(&self) -> &str {
+        "omarcord"
+    }
+
+    fn detect(&self, _program: &ProgramConfig) -> Option<InstalledProgram> {
+        ProgramDetector::detect_vencord()
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        // Vencord doesn't support symlinks, so the theme is written directly
+        let theme_file = installed.theme_dir.join(&program.output_file);
+
+        ctx.backup_file(&theme_file, &program.output_file)?;
+        ctx.write_file(&theme_file, rendered(files, &program.output_file)?)?;
+        info!("✓ Wrote Omarcord theme to Vencord: {:?}", theme_file);
+
+        Ok(())
+    }
+
+    fn activate(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        ThemeActivator::activate_vencord_with_name(ctx, installed, &program.output_file)
+    }
+
+    fn deactivate(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+    ) -> Result<ActivationResult> {
+        ThemeActivator::deactivate_vencord_with_name(ctx, installed, &program.output_file)
+    }
+
+    fn targets(&self, program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        vec![installed.theme_dir.join(&program.output_file)]
+    }
+
+    fn verify(&self, program: &ProgramConfig, installed: &InstalledProgram) -> Verification {
+        let theme_file = installed.theme_dir.join(&program.output_file);
+        if !theme_file.exists() {
+            return Verification {
+                ok: false,
+                message: format!("Missing: {}", theme_file.display()),
+            };
+        }
+
+        // The theme only shows up once it's listed in Vencord's settings
+        let enabled = installed
+            .config_file
+            .as_ref()
+            .and_then(|settings| fs::read_to_string(settings).ok())
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .and_then(|settings| settings["enabledThemes"].as_array().cloned())
+            .is_some_and(|themes| themes.iter().any(|t| t.as_str() == Some(program.output_file.as_str())));
+
+        if enabled {
+            Verification {
+                ok: true,
+                message: "Deployed and enabled".to_string(),
+            }
+        } else {
+            Verification {
+                ok: false,
+                message: "Deployed but not enabled in Vencord settings".to_string(),
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Omarvscode: VS Code color theme extension
+
+use super::{rendered, DeployContext, ProgramBackend, RenderContext, RenderedFile};
+use crate::config::ProgramConfig;
+use crate::detector::{InstalledProgram, ProgramDetector};
+use anyhow::Result;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Template and generated file name of the extension manifest
+const PACKAGE_TEMPLATE: &str = "omarvscode-package";
+const PACKAGE_FILE: &str = "omarvscode-package.json";
+
+/// Theme path inside the extension, referenced by package.json
+const THEME_FILE: &str = "themes/omarvscode-color-theme.json";
+
+pub struct OmarvscodeBackend;
+
+impl ProgramBackend for OmarvscodeBackend {
+    fn name(&self) -> &str {
+        "omarvscode"
+    }
+
+    fn detect(&self, _program: &ProgramConfig) -> Option<InstalledProgram> {
+        ProgramDetector::detect_vscode()
+    }
+
+    fn templates(&self, program: &ProgramConfig) -> Vec<String> {
+        vec![program.template.clone(), PACKAGE_TEMPLATE.to_string()]
+    }
+
+    fn render(&self, ctx: &RenderContext, program: &ProgramConfig) -> Result<Vec<RenderedFile>> {
+        Ok(vec![
+            RenderedFile {
+                file_name: program.output_file.clone(),
+                content: ctx.render(&program.template, program)?,
+            },
+            RenderedFile {
+                file_name: PACKAGE_FILE.to_string(),
+                content: ctx.render(PACKAGE_TEMPLATE, program)?,
+            },
+        ])
+    }
+
+    fn deploy(
+        &self,
+        ctx: &DeployContext,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> Result<()> {
+        // The extension lives at ~/.vscode/extensions/local.theme-omarvscode/
+        let theme_file = installed.theme_dir.join(THEME_FILE);
+        ctx.backup_file(&theme_file, "omarvscode-color-theme.json")?;
+        ctx.write_file(&theme_file, rendered(files, &program.output_file)?)?;
+        info!("✓ Wrote VS Code theme to: {:?}", theme_file);
+
+        let package_file = installed.theme_dir.join("package.json");
+        ctx.write_file(&package_file, rendered(files, PACKAGE_FILE)?)?;
+        info!("✓ Wrote VS Code package.json to: {:?}", package_file);
+
+        info!("✓ VS Code theme ready! Reload VS Code window to see changes (Ctrl+Shift+P > Reload Window)");
+        info!("   Then select theme: Ctrl+Shift+P > Preferences: Color Theme > Omarvscode");
+
+        Ok(())
+    }
+
+    fn targets(&self, _program: &ProgramConfig, installed: &InstalledProgram) -> Vec<PathBuf> {
+        vec![
+            installed.theme_dir.join(THEME_FILE),
+            installed.theme_dir.join("package.json"),
+        ]
+    }
+}
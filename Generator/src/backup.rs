@@ -0,0 +1,436 @@
+use crate::atomic;
+use crate::config::BackupSettings;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Timestamp format of backup entries, shared with deployment manifests
+pub const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Serializes index updates between threads of this process
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// One backed-up version of a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub program: String,
+    /// Backup name, e.g. `cava_config`
+    pub name: String,
+    /// File that was backed up
+    pub source: PathBuf,
+    pub timestamp: String,
+    /// Object holding the content, relative to `objects/`
+    pub object: String,
+    pub size: u64,
+}
+
+/// Result of applying the retention policy
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    pub removed: Vec<BackupEntry>,
+    /// Objects deleted because no entry uses them any more
+    pub removed_objects: Vec<PathBuf>,
+    /// Deployment manifests dropped because they restore from a removed object
+    pub removed_deployments: Vec<PathBuf>,
+    pub freed_bytes: u64,
+}
+
+/// Content-addressed backup store
+///
+/// Each backed-up version is an entry in `index.json`; the content lives in
+/// `objects/<hash>`, so identical copies of a file are stored once.
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+impl BackupStore {
+    pub fn new(dir: &Path) -> Self {
+        BackupStore { dir: dir.to_path_buf() }
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.dir.join("objects")
+    }
+
+    /// Where `content` is (or would be) stored
+    pub fn object_path(&self, content: &[u8]) -> PathBuf {
+        self.objects_dir().join(content_hash(content))
+    }
+
+    /// Back up `source`, reusing the stored object if the content is already there
+    pub fn store(&self, program: &str, name: &str, source: &Path) -> Result<PathBuf> {
+        let content = fs::read(source).with_context(|| format!("Failed to read {:?}", source))?;
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut entries = self.entries()?;
+        self.import_legacy_into(&mut entries)?;
+
+        let object = self.write_object(&content)?;
+        entries.push(BackupEntry {
+            program: program.to_string(),
+            name: name.to_string(),
+            source: source.to_path_buf(),
+            timestamp: Local::now().format(TIMESTAMP_FORMAT).to_string(),
+            object: object.clone(),
+            size: content.len() as u64,
+        });
+        self.save_index(&entries)?;
+
+        Ok(self.objects_dir().join(object))
+    }
+
+    /// Store `content` as an object unless it is there already; returns its name
+    fn write_object(&self, content: &[u8]) -> Result<String> {
+        let objects_dir = self.objects_dir();
+        fs::create_dir_all(&objects_dir)
+            .with_context(|| format!("Failed to create directory: {:?}", objects_dir))?;
+
+        // Same hash, different content: pick the next free suffix
+        let hash = content_hash(content);
+        let mut object = hash.clone();
+        let mut n = 1;
+        loop {
+            let path = objects_dir.join(&object);
+            match fs::read(&path) {
+                Ok(existing) if existing == content => return Ok(object),
+                Ok(_) => {
+                    n += 1;
+                    object = format!("{}-{}", hash, n);
+                }
+                Err(_) => {
+                    atomic::write_atomic(&path, content)?;
+                    return Ok(object);
+                }
+            }
+        }
+    }
+
+    /// Timestamped copies (`cava_config.20250101_120000`) that older versions
+    /// wrote straight into the backup directory, oldest first, as entries
+    /// whose `source` is the backup name in that directory
+    fn legacy_backups(&self) -> Vec<(PathBuf, BackupEntry)> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut legacy: Vec<(PathBuf, BackupEntry)> = dir
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|path| path.is_file())
+            .filter_map(|path| {
+                let file_name = path.file_name()?.to_str()?;
+                let (head, timestamp) = file_name.split_at_checked(file_name.len().checked_sub(15)?)?;
+                let name = head.strip_suffix(['.', '_']).filter(|name| !name.is_empty())?;
+                NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+
+                let entry = BackupEntry {
+                    program: name.to_string(),
+                    name: name.to_string(),
+                    source: self.dir.join(name),
+                    timestamp: timestamp.to_string(),
+                    object: String::new(),
+                    size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                };
+                Some((path, entry))
+            })
+            .collect();
+
+        legacy.sort_by(|(_, a), (_, b)| a.timestamp.cmp(&b.timestamp));
+        legacy
+    }
+
+    /// Move the legacy copies into the store, keeping their timestamps
+    fn import_legacy_into(&self, entries: &mut Vec<BackupEntry>) -> Result<()> {
+        let legacy = self.legacy_backups();
+        if legacy.is_empty() {
+            return Ok(());
+        }
+
+        for (path, mut entry) in legacy {
+            let content = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            entry.object = self.write_object(&content)?;
+            entries.push(entry);
+            self.save_index(entries)?;
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+            info!("✓ Imported old backup {:?}", path);
+        }
+
+        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        self.save_index(entries)
+    }
+
+    /// Move timestamped copies left by older versions into the store
+    pub fn import_legacy(&self) -> Result<()> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.entries()?;
+        self.import_legacy_into(&mut entries)
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> Result<Vec<BackupEntry>> {
+        let path = self.index_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    fn save_index(&self, entries: &[BackupEntry]) -> Result<()> {
+        atomic::write_atomic(&self.index_path(), serde_json::to_string_pretty(entries)?)
+    }
+
+    /// Entries grouped by program, oldest first within each group
+    pub fn by_program(&self) -> Result<BTreeMap<String, Vec<BackupEntry>>> {
+        let mut groups: BTreeMap<String, Vec<BackupEntry>> = BTreeMap::new();
+        for entry in self.entries()? {
+            groups.entry(entry.program.clone()).or_default().push(entry);
+        }
+        Ok(groups)
+    }
+
+    /// Bytes used by stored objects
+    pub fn disk_usage(&self) -> u64 {
+        fs::read_dir(self.objects_dir())
+            .map(|dir| {
+                dir.filter_map(|e| e.ok()?.metadata().ok())
+                    .map(|m| m.len())
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Drop entries outside the retention policy and objects no entry uses
+    /// Timestamped copies left by older versions are imported first.
+    /// With `dry_run` nothing is changed and the report lists what would be.
+    pub fn prune(&self, settings: &BackupSettings, dry_run: bool) -> Result<PruneReport> {
+        let _lock = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.entries()?;
+        if dry_run {
+            // Planned as if imported, each legacy copy standing in for its
+            // object by its absolute path
+            for (path, entry) in self.legacy_backups() {
+                entries.push(BackupEntry {
+                    object: path.to_string_lossy().to_string(),
+                    ..entry
+                });
+            }
+            entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        } else {
+            self.import_legacy_into(&mut entries)?;
+        }
+        let now = Local::now().naive_local();
+
+        let (kept, removed) = apply_retention(entries, settings, now);
+        let mut report = PruneReport {
+            removed,
+            ..PruneReport::default()
+        };
+
+        if report.removed.is_empty() {
+            return Ok(report);
+        }
+
+        let in_use: HashSet<&str> = kept.iter().map(|e| e.object.as_str()).collect();
+        let mut orphaned: Vec<&str> = report
+            .removed
+            .iter()
+            .map(|e| e.object.as_str())
+            .filter(|object| !in_use.contains(object))
+            .collect();
+        orphaned.sort();
+        orphaned.dedup();
+
+        for object in &orphaned {
+            let path = self.objects_dir().join(object);
+            report.freed_bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            if !dry_run {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!("Failed to remove backup {:?}: {}", path, e);
+                }
+            }
+            report.removed_objects.push(path);
+        }
+
+        if !dry_run {
+            self.save_index(&kept)?;
+            info!(
+                "✓ Pruned {} backups ({} bytes freed)",
+                report.removed.len(),
+                report.freed_bytes
+            );
+        }
+
+        Ok(report)
+    }
+}
+
+/// Split entries into kept and removed: per source file the newest
+/// `keep_last` are kept, minus those older than `max_age_days`, but never the newest one
+fn apply_retention(
+    entries: Vec<BackupEntry>,
+    settings: &BackupSettings,
+    now: NaiveDateTime,
+) -> (Vec<BackupEntry>, Vec<BackupEntry>) {
+    let mut seen: BTreeMap<PathBuf, usize> = BTreeMap::new();
+    let mut keep = vec![false; entries.len()];
+
+    for (i, entry) in entries.iter().enumerate().rev() {
+        let newer = seen.entry(entry.source.clone()).or_insert(0);
+        let within_count = settings.keep_last == 0 || *newer < settings.keep_last;
+        let within_age = settings.max_age_days.is_none_or(|days| {
+            NaiveDateTime::parse_from_str(&entry.timestamp, TIMESTAMP_FORMAT)
+                .map(|t| (now - t).num_days() < days as i64)
+                .unwrap_or(true)
+        });
+
+        keep[i] = *newer == 0 || (within_count && within_age);
+        *newer += 1;
+    }
+
+    let (kept, removed): (Vec<_>, Vec<_>) = entries.into_iter().zip(keep).partition(|(_, keep)| *keep);
+    (
+        kept.into_iter().map(|(e, _)| e).collect(),
+        removed.into_iter().map(|(e, _)| e).collect(),
+    )
+}
+
+/// FNV-1a hash of file content, 16 hex digits
+pub fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in content {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(source: &str, timestamp: &str, object: &str) -> BackupEntry {
+        BackupEntry {
+            program: "omarcava".to_string(),
+            name: "cava_config".to_string(),
+            source: PathBuf::from(source),
+            timestamp: timestamp.to_string(),
+            object: object.to_string(),
+            size: 1,
+        }
+    }
+
+    #[test]
+    fn test_store_deduplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = BackupStore::new(&temp_dir.path().join("backups"));
+        let source = temp_dir.path().join("config");
+
+        fs::write(&source, "theme a").unwrap();
+        let first = store.store("omarcava", "cava_config", &source).unwrap();
+        let second = store.store("omarcava", "cava_config", &source).unwrap();
+        fs::write(&source, "theme b").unwrap();
+        let third = store.store("omarcava", "cava_config", &source).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, third);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "theme a");
+        assert_eq!(store.entries().unwrap().len(), 3);
+        assert_eq!(fs::read_dir(store.objects_dir()).unwrap().count(), 2);
+        assert_eq!(store.by_program().unwrap()["omarcava"].len(), 3);
+    }
+
+    #[test]
+    fn test_retention() {
+        let now = NaiveDateTime::parse_from_str("20250110_120000", TIMESTAMP_FORMAT).unwrap();
+        let entries = vec![
+            entry("/a", "20250101_120000", "1"),
+            entry("/a", "20250108_120000", "2"),
+            entry("/a", "20250109_120000", "3"),
+            entry("/b", "20241201_120000", "4"),
+        ];
+
+        let by_count = BackupSettings {
+            keep_last: 2,
+            ..BackupSettings::default()
+        };
+        let (kept, removed) = apply_retention(entries.clone(), &by_count, now);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(removed[0].object, "1");
+
+        // The newest copy of /b survives even though it is too old
+        let by_age = BackupSettings {
+            keep_last: 0,
+            max_age_days: Some(5),
+            ..BackupSettings::default()
+        };
+        let (kept, _) = apply_retention(entries, &by_age, now);
+        let objects: Vec<&str> = kept.iter().map(|e| e.object.as_str()).collect();
+        assert_eq!(objects, vec!["2", "3", "4"]);
+    }
+
+    #[test]
+    fn test_prune_removes_unused_objects() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = BackupStore::new(&temp_dir.path().join("backups"));
+        let source = temp_dir.path().join("config");
+
+        for content in ["a", "b", "a"] {
+            fs::write(&source, content).unwrap();
+            store.store("omarcava", "cava_config", &source).unwrap();
+        }
+
+        let settings = BackupSettings {
+            keep_last: 1,
+            ..BackupSettings::default()
+        };
+        let planned = store.prune(&settings, true).unwrap();
+        assert_eq!(planned.removed.len(), 2);
+        assert_eq!(store.entries().unwrap().len(), 3);
+
+        // "a" is still used by the newest entry, only "b" goes
+        let report = store.prune(&settings, false).unwrap();
+        assert_eq!(report.removed_objects.len(), 1);
+        assert_eq!(store.entries().unwrap().len(), 1);
+        assert_eq!(fs::read_dir(store.objects_dir()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_prune_imports_legacy_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("backups");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cava_config.20250101_120000"), "a").unwrap();
+        fs::write(dir.join("cava_config.20250102_120000"), "b").unwrap();
+        fs::write(dir.join("color.ini_20250103_120000"), "c").unwrap();
+        fs::write(dir.join("notes.txt"), "mine").unwrap();
+
+        let store = BackupStore::new(&dir);
+        let settings = BackupSettings {
+            keep_last: 1,
+            ..BackupSettings::default()
+        };
+
+        let planned = store.prune(&settings, true).unwrap();
+        assert_eq!(planned.removed.len(), 1);
+        assert_eq!(planned.removed_objects, vec![dir.join("cava_config.20250101_120000")]);
+        assert!(dir.join("cava_config.20250101_120000").exists());
+
+        let report = store.prune(&settings, false).unwrap();
+        assert_eq!(report.removed[0].timestamp, "20250101_120000");
+        let names: Vec<String> = store.entries().unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["cava_config", "color.ini"]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        assert!(dir.join("notes.txt").exists());
+    }
+}
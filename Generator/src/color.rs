@@ -1,3 +1,4 @@
+use crate::backup::content_hash;
 use anyhow::Result;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -26,7 +27,7 @@ impl Color {
         // Normalize to 6-character format
         let normalized = if hex.len() == 3 {
             hex.chars()
-                .flat_map(|c| std::iter::repeat(c).take(2))
+                .flat_map(|c| std::iter::repeat_n(c, 2))
                 .collect()
         } else {
             hex.to_string()
@@ -41,13 +42,11 @@ impl Color {
     }
 
     /// Get hex value without #
-    #[allow(dead_code)]
     pub fn hex_no_hash(&self) -> &str {
         self.0.trim_start_matches('#')
     }
 
     /// Convert to RGB values (r, g, b) where each is 0-255
-    #[allow(dead_code)]
     pub fn to_rgb(&self) -> Result<(u8, u8, u8)> {
         let hex = self.hex_no_hash();
         let r = u8::from_str_radix(&hex[0..2], 16)?;
@@ -57,11 +56,52 @@ impl Color {
     }
 
     /// Convert to RGB string "r, g, b"
-    #[allow(dead_code)]
     pub fn to_rgb_string(&self) -> Result<String> {
         let (r, g, b) = self.to_rgb()?;
         Ok(format!("{}, {}, {}", r, g, b))
     }
+
+    /// Convert to HSL (hue in degrees 0-360, saturation and lightness 0-1)
+    pub fn to_hsl(&self) -> Result<(f64, f64, f64)> {
+        let (r, g, b) = self.to_rgb()?;
+        let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let l = (max + min) / 2.0;
+
+        if delta == 0.0 {
+            return Ok((0.0, 0.0, l));
+        }
+
+        let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * (((g - b) / delta).rem_euclid(6.0))
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        Ok((h, s, l))
+    }
+
+    /// Relative luminance as defined by WCAG (0 = black, 1 = white)
+    pub fn luminance(&self) -> Result<f64> {
+        let (r, g, b) = self.to_rgb()?;
+
+        let channel = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        Ok(0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b))
+    }
 }
 
 impl FromStr for Color {
@@ -109,6 +149,31 @@ pub struct ColorPalette {
 }
 
 impl ColorPalette {
+    /// Names of the standard palette slots, in declaration order
+    pub const SLOTS: [&'static str; 21] = [
+        "background",
+        "foreground",
+        "black",
+        "red",
+        "green",
+        "yellow",
+        "blue",
+        "magenta",
+        "cyan",
+        "white",
+        "bright_black",
+        "bright_red",
+        "bright_green",
+        "bright_yellow",
+        "bright_blue",
+        "bright_magenta",
+        "bright_cyan",
+        "bright_white",
+        "cursor",
+        "selection_background",
+        "selection_foreground",
+    ];
+
     /// Extract hex colors from text using regex
     #[allow(dead_code)]
     pub fn extract_hex_colors(text: &str) -> Result<Vec<Color>> {
@@ -164,8 +229,30 @@ impl ColorPalette {
         }
     }
 
+    /// Stable hash of the palette contents (FNV-1a, 16 hex digits)
+    /// Independent of the order custom colors were inserted in
+    pub fn content_hash(&self) -> String {
+        let mut entries: Vec<(&str, &str)> = Self::SLOTS
+            .iter()
+            .filter_map(|name| self.get(name).map(|c| (*name, c.hex())))
+            .collect();
+
+        let mut custom: Vec<(&str, &str)> = self
+            .custom
+            .iter()
+            .map(|(name, c)| (name.as_str(), c.hex()))
+            .collect();
+        custom.sort();
+        entries.extend(custom);
+
+        let content: String = entries
+            .into_iter()
+            .map(|(name, hex)| format!("{}={};", name, hex))
+            .collect();
+        content_hash(content.as_bytes())
+    }
+
     /// Get a color by name (checking both standard and custom colors)
-    #[allow(dead_code)]
     pub fn get(&self, name: &str) -> Option<&Color> {
         match name {
             "background" => self.background.as_ref(),
@@ -219,6 +306,37 @@ mod tests {
         assert_eq!(color.to_rgb().unwrap(), (0, 255, 0));
     }
 
+    #[test]
+    fn test_color_hsl_and_luminance() {
+        let (h, s, l) = Color::new("#ff0000").unwrap().to_hsl().unwrap();
+        assert_eq!((h.round(), s, l), (0.0, 1.0, 0.5));
+
+        let (h, _, _) = Color::new("#0000ff").unwrap().to_hsl().unwrap();
+        assert_eq!(h.round(), 240.0);
+
+        assert_eq!(Color::new("#000000").unwrap().luminance().unwrap(), 0.0);
+        assert!((Color::new("#ffffff").unwrap().luminance().unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut a = ColorPalette {
+            background: Some(Color::new("#000000").unwrap()),
+            ..Default::default()
+        };
+        let mut b = a.clone();
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        a.custom.insert("one".to_string(), Color::new("#111111").unwrap());
+        a.custom.insert("two".to_string(), Color::new("#222222").unwrap());
+        b.custom.insert("two".to_string(), Color::new("#222222").unwrap());
+        b.custom.insert("one".to_string(), Color::new("#111111").unwrap());
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.red = Some(Color::new("#ff0000").unwrap());
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
     #[test]
     fn test_extract_hex_colors() {
         let text = "background = \"#ff0000\" foreground = \"#00ff00\"";
@@ -1,3 +1,4 @@
+use crate::atomic;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -15,6 +16,10 @@ pub struct Config {
     #[serde(default)]
     pub programs: Vec<ProgramConfig>,
 
+    /// Directory with all installed Omarchy themes, used by `generate --all`
+    #[serde(default = "default_themes_dir")]
+    pub themes_dir: PathBuf,
+
     /// Priority order for color extraction sources
     #[serde(default = "default_color_priority")]
     pub color_priority: Vec<String>,
@@ -34,6 +39,106 @@ pub struct Config {
     /// Automatically create symlinks to theme directories
     #[serde(default = "default_true")]
     pub auto_symlink: bool,
+
+    /// Fail instead of using template defaults when a palette slot is missing
+    #[serde(default)]
+    pub strict_templates: bool,
+
+    /// Directories with user templates that override or extend the embedded ones
+    #[serde(default = "default_template_dirs")]
+    pub template_dirs: Vec<PathBuf>,
+
+    /// Where replaced files are backed up and how long the copies are kept
+    #[serde(default)]
+    pub backups: BackupSettings,
+
+    /// Where a manifest of every deployment is kept for `history` and `rollback`
+    #[serde(default = "default_deployments_dir")]
+    pub deployments_dir: PathBuf,
+
+    /// What to do with the other programs when one fails to deploy
+    #[serde(default)]
+    pub on_deploy_failure: FailurePolicy,
+
+    /// Shell command run once before any program is deployed; if it fails nothing is deployed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_deploy: Option<String>,
+
+    /// Shell command run once after a deployment that changed something
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_deploy: Option<String>,
+
+    /// Seconds a hook or activation command may run before it is killed
+    #[serde(default = "default_hook_timeout_secs")]
+    pub hook_timeout_secs: u64,
+
+    /// Seconds a program's deploy may take before it is reported as failed
+    #[serde(default = "default_deploy_timeout_secs")]
+    pub deploy_timeout_secs: u64,
+
+    /// Milliseconds without file events before watch mode acts on a burst of them
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+/// The `[backups]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// Backup store directory
+    #[serde(default = "default_backup_dir")]
+    pub dir: PathBuf,
+
+    /// Backups kept per file, newest first (0 keeps all)
+    #[serde(default = "default_keep_last")]
+    pub keep_last: usize,
+
+    /// Drop backups older than this many days; the newest copy of each file is always kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            dir: default_backup_dir(),
+            keep_last: default_keep_last(),
+            max_age_days: None,
+        }
+    }
+}
+
+fn default_backup_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".config/omarchy-themes/backups")
+}
+
+fn default_keep_last() -> usize {
+    10
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    30
+}
+
+fn default_deploy_timeout_secs() -> u64 {
+    120
+}
+
+fn default_debounce_ms() -> u64 {
+    300
+}
+
+/// How a failed program deploy affects the rest of the deployment
+/// The failed program's own changes are always undone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    /// Keep the programs that deployed and carry on with the others
+    #[default]
+    Continue,
+    /// Undo every other program of the deployment
+    Rollback,
 }
 
 fn default_watch_path() -> PathBuf {
@@ -42,6 +147,12 @@ fn default_watch_path() -> PathBuf {
         .join(".config/omarchy/current/theme")
 }
 
+fn default_themes_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".config/omarchy/themes")
+}
+
 fn default_color_priority() -> Vec<String> {
     vec![
         "alacritty.toml".to_string(),
@@ -50,18 +161,34 @@ fn default_color_priority() -> Vec<String> {
     ]
 }
 
+fn default_template_dirs() -> Vec<PathBuf> {
+    vec![dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".config/omarchy-theme-watcher/templates")]
+}
+
 fn default_generated_themes_dir() -> PathBuf {
     dirs::home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".config/omarchy-themes/generated")
 }
 
+fn default_deployments_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".config/omarchy-themes/deployments")
+}
+
 /// Configuration for an external program
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ProgramConfig {
     /// Name of the program (e.g., "spicetify", "vencord")
     pub name: String,
 
+    /// Backend to deploy with, defaults to the one matching `name` ("file" for generic files)
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+
     /// Whether this program is enabled
     #[serde(default = "default_true")]
     pub enabled: bool,
@@ -75,6 +202,133 @@ pub struct ProgramConfig {
     /// Additional template variables (optional)
     #[serde(default)]
     pub variables: std::collections::HashMap<String, String>,
+
+    /// Where a "file" program is deployed, `~` and `$VAR` are expanded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Octal permissions for the deployed file (e.g. "0644")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+
+    /// Whether to back up the file being replaced
+    #[serde(default, skip_serializing_if = "BackupPolicy::is_inherit")]
+    pub backup: BackupPolicy,
+
+    /// Shell command to run before the program is deployed; if it fails nothing is written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_deploy: Option<String>,
+
+    /// Shell command to run after the program is deployed; if it fails the deploy is undone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_deploy: Option<String>,
+
+    /// Only deploy when the program is found this way
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detect: Option<DetectRule>,
+}
+
+/// When to back up a file before it is replaced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupPolicy {
+    /// Follow the global `create_backups` setting
+    #[default]
+    Inherit,
+    Always,
+    Never,
+}
+
+impl BackupPolicy {
+    fn is_inherit(&self) -> bool {
+        *self == BackupPolicy::Inherit
+    }
+
+    /// Whether to back up, given the global `create_backups` setting
+    pub fn enabled(&self, create_backups: bool) -> bool {
+        match self {
+            BackupPolicy::Inherit => create_backups,
+            BackupPolicy::Always => true,
+            BackupPolicy::Never => false,
+        }
+    }
+}
+
+/// How to tell whether a program is installed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DetectRule {
+    /// Executable that must be on `PATH`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+
+    /// Path that must exist, `~` and `$VAR` are expanded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl ProgramConfig {
+    /// Name of the backend that deploys this program
+    pub fn backend_name(&self) -> &str {
+        self.kind.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Parsed `mode`, if set
+    pub fn file_mode(&self) -> Result<Option<u32>> {
+        self.mode
+            .as_deref()
+            .map(|mode| {
+                u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+                    .with_context(|| format!("Invalid file mode {:?} for {}, expected octal like \"0644\"", mode, self.name))
+            })
+            .transpose()
+    }
+}
+
+/// Expand a leading `~` and `$VAR`/`${VAR}` references in a path
+pub fn expand_path(path: &str) -> Result<PathBuf> {
+    let mut expanded = String::new();
+    let mut chars = path.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+
+        let mut name = String::new();
+        while let Some(&next) = chars.peek() {
+            if next.is_ascii_alphanumeric() || next == '_' {
+                name.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if braced && chars.next() != Some('}') {
+            anyhow::bail!("Unterminated ${{...}} in path: {}", path);
+        }
+        if name.is_empty() {
+            expanded.push('$');
+            continue;
+        }
+
+        let value = std::env::var(&name)
+            .with_context(|| format!("Environment variable ${} in {:?} is not set", name, path))?;
+        expanded.push_str(&value);
+    }
+
+    if expanded == "~" || expanded.starts_with("~/") {
+        let home = dirs::home_dir().context("Could not determine home directory")?;
+        return Ok(home.join(expanded.trim_start_matches('~').trim_start_matches('/')));
+    }
+
+    Ok(PathBuf::from(expanded))
 }
 
 fn default_true() -> bool {
@@ -91,42 +345,53 @@ impl Default for Config {
                     enabled: true,
                     output_file: "omarcord.theme.css".to_string(),
                     template: "omarcord".to_string(),
-                    variables: std::collections::HashMap::new(),
+                    ..ProgramConfig::default()
                 },
                 ProgramConfig {
                     name: "omarchify".to_string(),
                     enabled: true,
                     output_file: "color.ini".to_string(),
                     template: "omarchify".to_string(),
-                    variables: std::collections::HashMap::new(),
+                    ..ProgramConfig::default()
                 },
                 ProgramConfig {
                     name: "omarcava".to_string(),
                     enabled: true,
                     output_file: "config".to_string(),
                     template: "omarcava".to_string(),
-                    variables: std::collections::HashMap::new(),
+                    ..ProgramConfig::default()
                 },
                 ProgramConfig {
                     name: "omarclock".to_string(),
                     enabled: true,
                     output_file: "omarclock.sh".to_string(),
                     template: "omarclock".to_string(),
-                    variables: std::collections::HashMap::new(),
+                    ..ProgramConfig::default()
                 },
                 ProgramConfig {
                     name: "omarvscode".to_string(),
                     enabled: true,
                     output_file: "omarvscode-color-theme.json".to_string(),
                     template: "omarvscode".to_string(),
-                    variables: std::collections::HashMap::new(),
+                    ..ProgramConfig::default()
                 },
             ],
+            themes_dir: default_themes_dir(),
             color_priority: default_color_priority(),
             generated_themes_dir: default_generated_themes_dir(),
             auto_activate: true,
             create_backups: true,
             auto_symlink: true,
+            strict_templates: false,
+            template_dirs: default_template_dirs(),
+            backups: BackupSettings::default(),
+            deployments_dir: default_deployments_dir(),
+            on_deploy_failure: FailurePolicy::default(),
+            pre_deploy: None,
+            post_deploy: None,
+            hook_timeout_secs: default_hook_timeout_secs(),
+            deploy_timeout_secs: default_deploy_timeout_secs(),
+            debounce_ms: default_debounce_ms(),
         }
     }
 }
@@ -163,7 +428,7 @@ impl Config {
         let content = toml::to_string_pretty(self)
             .context("Failed to serialize config")?;
 
-        fs::write(path, content)
+        atomic::write_atomic(path, content)
             .with_context(|| format!("Failed to write config file: {:?}", path))?;
 
         info!("Saved configuration to {:?}", path);
@@ -195,6 +460,45 @@ impl Config {
     pub fn enabled_programs(&self) -> impl Iterator<Item = &ProgramConfig> {
         self.programs.iter().filter(|p| p.enabled)
     }
+
+    /// Check settings that parse but can't work
+    pub fn validate(&self) -> Result<()> {
+        for source in &self.color_priority {
+            if !["alacritty.toml", "btop.theme", "custom_theme.json"].contains(&source.as_str()) {
+                anyhow::bail!("Unknown color source {:?} in color_priority", source);
+            }
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for program in &self.programs {
+            if !names.insert(&program.name) {
+                anyhow::bail!("Program {:?} is configured twice", program.name);
+            }
+            program.file_mode()?;
+            if program.backend_name() == "file" && program.target.is_none() {
+                anyhow::bail!("File program {:?} has no target", program.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enabled programs whose output may differ from what `old` deployed
+    /// Settings shared by all programs mark every one of them as changed.
+    pub fn changed_programs(&self, old: &Config) -> Vec<String> {
+        let shared_changed = self.color_priority != old.color_priority
+            || self.generated_themes_dir != old.generated_themes_dir
+            || self.template_dirs != old.template_dirs
+            || self.strict_templates != old.strict_templates
+            || self.auto_activate != old.auto_activate
+            || self.auto_symlink != old.auto_symlink
+            || self.create_backups != old.create_backups;
+
+        self.enabled_programs()
+            .filter(|program| shared_changed || !old.enabled_programs().any(|p| p == *program))
+            .map(|program| program.name.clone())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -205,10 +509,84 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = Config::default();
-        assert!(config.programs.len() > 0);
+        assert!(!config.programs.is_empty());
         assert!(config.color_priority.contains(&"alacritty.toml".to_string()));
     }
 
+    #[test]
+    fn test_file_program_config() {
+        let config: Config = toml::from_str(
+            r#"
+            on_deploy_failure = "rollback"
+            post_deploy = "notify-send theme"
+            hook_timeout_secs = 5
+
+            [[programs]]
+            name = "waybar"
+            type = "file"
+            output_file = "waybar.css"
+            template = "waybar"
+            target = "~/.config/waybar/colors.css"
+            mode = "0600"
+            backup = "never"
+            post_deploy = "pkill -SIGUSR2 waybar"
+            detect = { binary = "waybar" }
+            "#,
+        )
+        .unwrap();
+
+        let program = &config.programs[0];
+        assert_eq!(program.backend_name(), "file");
+        assert_eq!(program.file_mode().unwrap(), Some(0o600));
+        assert!(!program.backup.enabled(true));
+        assert_eq!(program.detect.as_ref().unwrap().binary.as_deref(), Some("waybar"));
+        assert!(config.programs.iter().all(|p| p.enabled));
+        assert_eq!(config.on_deploy_failure, FailurePolicy::Rollback);
+        assert_eq!(config.post_deploy.as_deref(), Some("notify-send theme"));
+        assert_eq!(config.hook_timeout_secs, 5);
+
+        // Built-in programs keep serializing without the generic fields
+        let saved = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(!saved.contains("backup ="));
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_path("~/a/b").unwrap(), home.join("a/b"));
+
+        std::env::set_var("OMARCHY_TEST_EXPAND", "/tmp/x");
+        assert_eq!(expand_path("$OMARCHY_TEST_EXPAND/c").unwrap(), PathBuf::from("/tmp/x/c"));
+        assert_eq!(expand_path("${OMARCHY_TEST_EXPAND}d").unwrap(), PathBuf::from("/tmp/xd"));
+        assert!(expand_path("$OMARCHY_TEST_UNSET_VAR/c").is_err());
+    }
+
+    #[test]
+    fn test_validate_and_changed_programs() {
+        let mut old = Config::default();
+        old.programs[3].enabled = false;
+        assert!(old.validate().is_ok());
+
+        let mut config = old.clone();
+        assert!(config.changed_programs(&old).is_empty());
+
+        // Enabling a program or changing its variables changes only that program
+        config.programs[3].enabled = true;
+        config.programs[1].variables.insert("theme_name".to_string(), "Other".to_string());
+        let name = |i: usize| config.programs[i].name.clone();
+        assert_eq!(config.changed_programs(&old), vec![name(1), name(3)]);
+
+        config.color_priority.reverse();
+        assert_eq!(config.changed_programs(&old).len(), config.enabled_programs().count());
+
+        config.color_priority.push("kitty.conf".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = old.clone();
+        config.programs.push(old.programs[0].clone());
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_save_and_load_config() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -0,0 +1,379 @@
+use crate::generator::DeployReport;
+use crate::watcher::{Command, WatchHandle};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+/// Socket of the running daemon, under `$XDG_RUNTIME_DIR`
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir().map(|dir| dir.join("omarchy-theme-gen.sock"))
+}
+
+/// A request to the daemon, one JSON object per line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Status,
+    /// The report of the last finished deploy
+    Report,
+    Regenerate,
+    Pause,
+    Resume,
+    ReloadConfig,
+    /// Stream `WatchEvent`s until the client disconnects
+    Subscribe,
+}
+
+impl Request {
+    /// Parse a `ctl` subcommand
+    pub fn parse(command: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::json!({ "command": command })).ok()
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Request::Status => "status",
+            Request::Report => "report",
+            Request::Regenerate => "regenerate",
+            Request::Pause => "pause",
+            Request::Resume => "resume",
+            Request::ReloadConfig => "reload-config",
+            Request::Subscribe => "subscribe",
+        };
+        f.pad(name)
+    }
+}
+
+/// A response from the daemon, one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    Status(Status),
+    Report { report: Option<ReportSummary> },
+    /// The command was queued for the watch loop
+    Accepted { command: Request },
+    Event(WatchEvent),
+    Error { message: String },
+}
+
+/// What the daemon is doing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub pid: u32,
+    /// Theme the watched symlink points to
+    pub theme_dir: Option<PathBuf>,
+    pub paused: bool,
+    pub deploying: bool,
+    pub uptime_secs: u64,
+    pub deploys: usize,
+    /// Deploys with failed programs or errors
+    pub failed: usize,
+    pub superseded: usize,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match (self.paused, self.deploying) {
+            (true, _) => "paused",
+            (false, true) => "deploying",
+            (false, false) => "watching",
+        };
+        writeln!(f, "Daemon (pid {}) is {}, up {}s", self.pid, state, self.uptime_secs)?;
+        match &self.theme_dir {
+            Some(theme_dir) => writeln!(f, "  Theme: {}", theme_dir.display())?,
+            None => writeln!(f, "  Theme: none")?,
+        }
+        writeln!(
+            f,
+            "  Deploys: {} ({} with failures, {} superseded)",
+            self.deploys, self.failed, self.superseded
+        )
+    }
+}
+
+/// A deploy report as sent over the socket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportSummary {
+    pub theme_dir: PathBuf,
+    pub finished_at: String,
+    pub superseded: bool,
+    pub failed: usize,
+    pub programs: Vec<ProgramSummary>,
+    /// The report as printed by `once`
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramSummary {
+    pub program: String,
+    pub status: String,
+    pub message: String,
+}
+
+impl From<&DeployReport> for ReportSummary {
+    fn from(report: &DeployReport) -> Self {
+        ReportSummary {
+            theme_dir: report.theme_dir.clone(),
+            finished_at: chrono::Local::now().to_rfc3339(),
+            superseded: report.superseded,
+            failed: report.failed(),
+            programs: report
+                .programs
+                .iter()
+                .map(|p| ProgramSummary {
+                    program: p.program.clone(),
+                    status: p.status.to_string(),
+                    message: p.message.clone(),
+                })
+                .collect(),
+            text: report.to_string(),
+        }
+    }
+}
+
+/// Something the watcher did, sent to subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum WatchEvent {
+    DeployStarted,
+    DeployFinished { report: ReportSummary },
+    DeployFailed { error: String },
+    Paused,
+    Resumed,
+    ConfigReloaded { changed: Vec<String> },
+    ConfigRejected { error: String },
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchEvent::DeployStarted => write!(f, "Deploy started"),
+            WatchEvent::DeployFinished { report } => write!(f, "{}", report.text.trim_end()),
+            WatchEvent::DeployFailed { error } => write!(f, "Deploy failed: {}", error),
+            WatchEvent::Paused => write!(f, "Paused"),
+            WatchEvent::Resumed => write!(f, "Resumed"),
+            WatchEvent::ConfigReloaded { changed } if changed.is_empty() => write!(f, "Config reloaded"),
+            WatchEvent::ConfigReloaded { changed } => write!(f, "Config reloaded, changed: {}", changed.join(", ")),
+            WatchEvent::ConfigRejected { error } => write!(f, "Config rejected: {}", error),
+        }
+    }
+}
+
+/// Listens on the control socket; the socket file is removed when dropped
+pub struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl ControlServer {
+    /// Bind the socket, replacing one left behind by a daemon that is gone
+    pub async fn bind(path: &Path) -> Result<Self> {
+        if UnixStream::connect(path).await.is_ok() {
+            anyhow::bail!("Another daemon is listening on {:?}", path);
+        }
+        if path.exists() {
+            fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {:?}", path))?;
+        }
+
+        let listener = UnixListener::bind(path).with_context(|| format!("Failed to bind {:?}", path))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict {:?}", path))?;
+        info!("Listening for commands on {:?}", path);
+
+        Ok(ControlServer {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// Answer clients until the task is dropped
+    pub async fn serve(self, handle: WatchHandle) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    let handle = handle.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, handle).await {
+                            debug!("Control connection closed: {:#}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept control connection: {}", e),
+            }
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+async fn handle_connection(stream: UnixStream, handle: WatchHandle) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let message = format!("Invalid request: {}", e);
+                write_response(&mut writer, &Response::Error { message }).await?;
+                continue;
+            }
+        };
+
+        if request == Request::Subscribe {
+            return stream_events(&mut writer, &handle).await;
+        }
+        write_response(&mut writer, &respond(&handle, request)).await?;
+    }
+
+    Ok(())
+}
+
+fn respond(handle: &WatchHandle, request: Request) -> Response {
+    let command = match request {
+        Request::Status => return Response::Status(handle.status()),
+        Request::Report => return Response::Report { report: handle.last_report() },
+        Request::Subscribe => unreachable!("subscriptions stream events"),
+        Request::Regenerate => Command::Regenerate,
+        Request::Pause => Command::Pause,
+        Request::Resume => Command::Resume,
+        Request::ReloadConfig => Command::ReloadConfig,
+    };
+
+    match handle.send(command) {
+        Ok(()) => Response::Accepted { command: request },
+        Err(e) => Response::Error { message: format!("{:#}", e) },
+    }
+}
+
+/// Send every event to a subscriber until it disconnects or the watcher stops
+async fn stream_events(writer: &mut OwnedWriteHalf, handle: &WatchHandle) -> Result<()> {
+    let mut events = handle.subscribe();
+    write_response(writer, &Response::Accepted { command: Request::Subscribe }).await?;
+
+    loop {
+        match events.recv().await {
+            Ok(event) => write_response(writer, &Response::Event(event)).await?,
+            Err(RecvError::Lagged(missed)) => warn!("Subscriber missed {} events", missed),
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+async fn write_response(writer: &mut OwnedWriteHalf, response: &Response) -> Result<()> {
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Connection to the daemon's control socket
+pub struct ControlClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl ControlClient {
+    /// Connect to the daemon, `None` when none is listening
+    pub async fn connect(path: &Path) -> Option<Self> {
+        let stream = UnixStream::connect(path).await.ok()?;
+        let (reader, writer) = stream.into_split();
+        Some(ControlClient {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    pub async fn send(&mut self, request: Request) -> Result<()> {
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await.context("Failed to send request")
+    }
+
+    /// Next response, `None` once the daemon closed the connection
+    pub async fn next(&mut self) -> Result<Option<Response>> {
+        let Some(line) = self.lines.next_line().await.context("Failed to read response")? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&line)
+            .map(Some)
+            .with_context(|| format!("Invalid response: {}", line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::generator::Generator;
+    use crate::watcher::ThemeWatcher;
+    use tempfile::TempDir;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_protocol_round_trip() {
+        assert_eq!(Request::parse("reload-config"), Some(Request::ReloadConfig));
+        assert_eq!(Request::parse("bogus"), None);
+        assert_eq!(Request::ReloadConfig.to_string(), "reload-config");
+        assert_eq!(serde_json::to_string(&Request::Status).unwrap(), r#"{"command":"status"}"#);
+
+        let response = Response::Event(WatchEvent::ConfigReloaded {
+            changed: vec!["waybar".to_string()],
+        });
+        let line = serde_json::to_string(&response).unwrap();
+        assert_eq!(line, r#"{"type":"event","event":"config-reloaded","changed":["waybar"]}"#);
+        let Response::Event(event) = serde_json::from_str(&line).unwrap() else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.to_string(), "Config reloaded, changed: waybar");
+    }
+
+    #[tokio::test]
+    async fn test_socket_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let socket = temp_dir.path().join("ctl.sock");
+        let generator = Generator::new(Config::default()).unwrap();
+        let watcher = ThemeWatcher::new(temp_dir.path().join("current"), generator);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let server = ControlServer::bind(&socket).await.unwrap();
+        let serving = tokio::spawn(server.serve(watcher.handle(tx)));
+
+        let mut client = ControlClient::connect(&socket).await.unwrap();
+        client.send(Request::Status).await.unwrap();
+        let Some(Response::Status(status)) = client.next().await.unwrap() else {
+            panic!("expected a status");
+        };
+        assert_eq!(status.pid, std::process::id());
+        assert!(!status.paused);
+
+        // Commands are queued for the watch loop
+        client.send(Request::Pause).await.unwrap();
+        assert!(matches!(
+            client.next().await.unwrap(),
+            Some(Response::Accepted { command: Request::Pause })
+        ));
+        assert_eq!(rx.recv().await, Some(Command::Pause));
+
+        // A second daemon doesn't take over the socket
+        assert!(ControlServer::bind(&socket).await.is_err());
+
+        serving.abort();
+        serving.await.ok();
+        assert!(!socket.exists());
+        assert!(ControlClient::connect(&socket).await.is_none());
+    }
+}
@@ -82,23 +82,6 @@ impl ProgramDetector {
         programs
     }
 
-    /// Detect programs by config name (omarcord -> vencord, omarchify -> spicetify, omarcava -> cava)
-    pub fn detect_by_config_name(config_name: &str) -> Option<InstalledProgram> {
-        match config_name {
-            "omarcord" => Self::detect_vencord(),
-            "omarchify" => Self::detect_spicetify(),
-            "omarcava" => Self::detect_cava(),
-            "omarclock" => Self::detect_tclock(),
-            "omarvscode" => Self::detect_vscode(),
-            "vencord" => Self::detect_vencord(),
-            "spicetify" => Self::detect_spicetify(),
-            "cava" => Self::detect_cava(),
-            "tclock" => Self::detect_tclock(),
-            "vscode" => Self::detect_vscode(),
-            _ => None,
-        }
-    }
-
     /// Detect Vencord installation
     pub fn detect_vencord() -> Option<InstalledProgram> {
         debug!("Detecting Vencord installation...");
@@ -341,21 +324,25 @@ impl ProgramDetector {
         }
     }
 
-    /// Get installed programs filtered by configuration
-    pub fn get_installed_enabled(
-        config_programs: &[crate::config::ProgramConfig],
-    ) -> Vec<InstalledProgram> {
-        let detected = Self::detect_all();
-
-        detected
-            .into_iter()
-            .filter(|installed| {
-                // Check if program is enabled in config
-                config_programs
-                    .iter()
-                    .any(|cfg| cfg.name == installed.name && cfg.enabled)
-            })
-            .collect()
+    /// Find an executable on `PATH` without running it
+    pub fn find_in_path(binary: &str) -> Option<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let is_executable = |path: &std::path::Path| {
+            path.metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        };
+
+        if binary.contains('/') {
+            let path = PathBuf::from(binary);
+            return is_executable(&path).then_some(path);
+        }
+
+        std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(binary))
+                .find(|path| is_executable(path))
+        })
     }
 }
 
@@ -379,5 +366,8 @@ mod tests {
         // Test with a command that probably doesn't exist
         let result = ProgramDetector::check_cli_available("nonexistent-command-12345");
         assert!(!result);
+
+        assert!(ProgramDetector::find_in_path("ls").is_some());
+        assert!(ProgramDetector::find_in_path("nonexistent-command-12345").is_none());
     }
 }
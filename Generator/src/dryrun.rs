@@ -0,0 +1,192 @@
+use similar::TextDiff;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A change the deploy pipeline would make
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Write a file; `previous` is `None` for a new file
+    Write {
+        path: PathBuf,
+        previous: Option<String>,
+        content: String,
+    },
+    Backup {
+        path: PathBuf,
+        backup: PathBuf,
+    },
+    SetMode {
+        path: PathBuf,
+        mode: u32,
+    },
+    Symlink {
+        link: PathBuf,
+        source: PathBuf,
+    },
+    Remove {
+        path: PathBuf,
+    },
+    Command {
+        command: String,
+    },
+}
+
+impl Action {
+    /// Build a write action, reading the current content to diff against
+    pub fn write(path: PathBuf, content: &str) -> Self {
+        let previous = fs::read(&path)
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+        Action::Write {
+            path,
+            previous,
+            content: content.to_string(),
+        }
+    }
+
+    /// Whether applying this action would change anything
+    pub fn is_change(&self) -> bool {
+        match self {
+            Action::Write { previous, content, .. } => previous.as_deref() != Some(content),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Write {
+                path,
+                previous: None,
+                content,
+            } => {
+                writeln!(f, "write {} (new file, {} lines)", path.display(), content.lines().count())?;
+                write!(f, "{}", unified_diff(path, "", content))
+            }
+            Action::Write {
+                path,
+                previous: Some(previous),
+                content,
+            } => {
+                if previous == content {
+                    return writeln!(f, "write {} (unchanged)", path.display());
+                }
+                writeln!(f, "write {}", path.display())?;
+                write!(f, "{}", unified_diff(path, previous, content))
+            }
+            Action::Backup { path, backup } => {
+                writeln!(f, "backup {} -> {}", path.display(), backup.display())
+            }
+            Action::SetMode { path, mode } => writeln!(f, "chmod {:o} {}", mode, path.display()),
+            Action::Symlink { link, source } => {
+                let current = fs::read_link(link).ok();
+                if current.as_deref() == Some(source.as_path()) {
+                    writeln!(f, "symlink {} -> {} (unchanged)", link.display(), source.display())
+                } else {
+                    writeln!(f, "symlink {} -> {}", link.display(), source.display())
+                }
+            }
+            Action::Remove { path } => writeln!(f, "remove {}", path.display()),
+            Action::Command { command } => writeln!(f, "run {}", command),
+        }
+    }
+}
+
+/// Unified diff between the current and planned content of a file
+pub fn unified_diff(path: &std::path::Path, old: &str, new: &str) -> String {
+    let old_header = if old.is_empty() {
+        "/dev/null".to_string()
+    } else {
+        path.display().to_string()
+    };
+
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_header, &path.display().to_string())
+        .to_string()
+}
+
+/// Records actions instead of performing them
+#[derive(Debug, Default)]
+pub struct DryRun {
+    actions: Mutex<Vec<Action>>,
+}
+
+impl DryRun {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an action that would have been performed
+    pub fn record(&self, action: Action) {
+        self.actions.lock().unwrap_or_else(|e| e.into_inner()).push(action);
+    }
+
+    /// All recorded actions, in order
+    pub fn actions(&self) -> Vec<Action> {
+        self.actions.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Print every recorded action with diffs for file writes
+    pub fn print_report(&self) {
+        let actions = self.actions();
+        let changes = actions.iter().filter(|a| a.is_change()).count();
+
+        println!();
+        println!("Dry run: {} actions, {} would change something (nothing was written)", actions.len(), changes);
+        println!("─────────────────────────────────────────");
+        for action in &actions {
+            print!("{}", action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_action_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config");
+        fs::write(&path, "a = 1\nb = 2\nc = 3\n").unwrap();
+
+        let action = Action::write(path.clone(), "a = 1\nb = 5\nc = 3\n");
+        assert!(action.is_change());
+
+        let report = action.to_string();
+        assert!(report.contains("-b = 2"));
+        assert!(report.contains("+b = 5"));
+        assert!(report.contains(" a = 1"));
+
+        let unchanged = Action::write(path, "a = 1\nb = 2\nc = 3\n");
+        assert!(!unchanged.is_change());
+        assert!(unchanged.to_string().contains("(unchanged)"));
+
+        let new_file = Action::write(temp_dir.path().join("new"), "x\n");
+        assert!(new_file.to_string().contains("--- /dev/null"));
+    }
+
+    #[test]
+    fn test_records_in_order() {
+        let dry_run = DryRun::new();
+        dry_run.record(Action::Command {
+            command: "spicetify apply".to_string(),
+        });
+        dry_run.record(Action::SetMode {
+            path: PathBuf::from("/tmp/x"),
+            mode: 0o755,
+        });
+
+        let actions = dry_run.actions();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].to_string(), "run spicetify apply\n");
+        assert_eq!(actions[1].to_string(), "chmod 755 /tmp/x\n");
+    }
+}
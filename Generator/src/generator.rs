@@ -1,13 +1,24 @@
-use crate::activator::ThemeActivator;
+use crate::activator::ActivationResult;
+use crate::backup::{BackupStore, PruneReport};
+use crate::backends::{BackendRegistry, DeployContext, ProgramBackend, RenderContext, RenderedFile, Verification};
 use crate::color::ColorPalette;
-use crate::config::{Config, ProgramConfig};
-use crate::detector::ProgramDetector;
+use crate::config::{Config, FailurePolicy, ProgramConfig};
+use crate::detector::InstalledProgram;
+use crate::dryrun::DryRun;
 use crate::extractor::{self, ColorSource};
-use crate::templates::TemplateRenderer;
+use crate::hooks::{self, HookResult};
+use crate::manifest::{self, DeployedState, Manifest, Reverted, RollbackTarget};
+use crate::templates::{self, Provenance, TemplateRenderer};
+use crate::transaction::{FileChange, Journal};
 use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
-use tracing::{info, warn};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
 /// Result of a generation operation
 #[derive(Debug)]
@@ -15,20 +26,398 @@ pub struct GenerationResult {
     pub program: String,
     pub output_file: PathBuf,
     pub success: bool,
+    /// The file already existed and was left alone
+    pub skipped: bool,
     pub message: String,
+    /// Palette slots the template referenced but the theme didn't provide
+    pub missing_slots: Vec<String>,
+}
+
+/// Results of generating the files of one theme in a batch
+#[derive(Debug)]
+pub struct ThemeGeneration {
+    pub theme_dir: PathBuf,
+    /// Per-program results, or why the theme couldn't be processed
+    pub results: Result<Vec<GenerationResult>, String>,
+}
+
+/// Detection and deployment state of a configured program
+#[derive(Debug)]
+pub struct ProgramStatus {
+    pub program: String,
+    pub enabled: bool,
+    /// Whether a backend handles this program
+    pub supported: bool,
+    pub installed: Option<InstalledProgram>,
+    pub verification: Option<Verification>,
+}
+
+/// Outcome of deploying one program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployStatus {
+    Deployed,
+    /// Rendered the same as what is deployed, so nothing was written or activated
+    Unchanged,
+    NotInstalled,
+    /// No backend handles the program's type
+    Unsupported,
+    /// The deploy failed and the program's changes were undone
+    Failed,
+    /// Deployed, then undone because another program failed
+    RolledBack,
+    /// Not attempted because a hook or, with the rollback policy, a render failed
+    Skipped,
+}
+
+impl DeployStatus {
+    fn symbol(&self) -> &'static str {
+        match self {
+            DeployStatus::Deployed => "✓",
+            DeployStatus::Unchanged => "=",
+            DeployStatus::NotInstalled | DeployStatus::Unsupported | DeployStatus::Skipped => "⊘",
+            DeployStatus::Failed => "✗",
+            DeployStatus::RolledBack => "↺",
+        }
+    }
+}
+
+impl fmt::Display for DeployStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            DeployStatus::Deployed => "deployed",
+            DeployStatus::Unchanged => "unchanged",
+            DeployStatus::NotInstalled => "not installed",
+            DeployStatus::Unsupported => "unsupported",
+            DeployStatus::Failed => "failed",
+            DeployStatus::RolledBack => "rolled back",
+            DeployStatus::Skipped => "skipped",
+        };
+        f.pad(label)
+    }
+}
+
+/// What happened to one program during a deployment
+#[derive(Debug)]
+pub struct ProgramReport {
+    pub program: String,
+    pub status: DeployStatus,
+    pub message: String,
+    /// Files written or replaced, with their backups
+    pub files: Vec<FileChange>,
+    pub missing_slots: Vec<String>,
+    pub activation: Option<ActivationResult>,
+    /// The program's `pre_deploy`/`post_deploy` hooks that ran
+    pub hooks: Vec<HookResult>,
+}
+
+impl ProgramReport {
+    fn new(program: &ProgramConfig, status: DeployStatus, message: impl Into<String>) -> Self {
+        ProgramReport {
+            program: program.name.clone(),
+            status,
+            message: message.into(),
+            files: Vec::new(),
+            missing_slots: Vec::new(),
+            activation: None,
+            hooks: Vec::new(),
+        }
+    }
+}
+
+/// Per-program results of deploying a theme
+#[derive(Debug)]
+pub struct DeployReport {
+    pub theme_dir: PathBuf,
+    pub programs: Vec<ProgramReport>,
+    /// Global `pre_deploy`/`post_deploy` hooks that ran
+    pub hooks: Vec<HookResult>,
+    /// Cancelled by a newer deploy before every program was deployed
+    pub superseded: bool,
+}
+
+impl DeployReport {
+    /// Number of programs whose deploy failed, plus failed global hooks
+    pub fn failed(&self) -> usize {
+        let programs = self
+            .programs
+            .iter()
+            .filter(|p| p.status == DeployStatus::Failed)
+            .count();
+        programs + self.hooks.iter().filter(|h| !h.success).count()
+    }
+}
+
+impl fmt::Display for DeployReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let theme = self.theme_dir.file_name().unwrap_or(self.theme_dir.as_os_str());
+        writeln!(f, "Deploy report for {}:", theme.to_string_lossy())?;
+
+        let width = self.programs.iter().map(|p| p.program.len()).max().unwrap_or(0);
+        for program in &self.programs {
+            let line = format!(
+                "  {} {:width$}  {:13}  {}",
+                program.status.symbol(),
+                program.program,
+                program.status,
+                program.message,
+                width = width
+            );
+            writeln!(f, "{}", line.trim_end())?;
+            if !program.missing_slots.is_empty() {
+                writeln!(f, "    template defaults used for: {}", program.missing_slots.join(", "))?;
+            }
+            for hook in program.hooks.iter().filter(|h| !h.success && !h.output.is_empty()) {
+                writeln!(f, "    {} output: {}", hook.name, hook.output.replace('\n', "\n      "))?;
+            }
+        }
+
+        if self.superseded {
+            writeln!(f, "  superseded by a newer deploy")?;
+        }
+
+        for hook in &self.hooks {
+            let symbol = if hook.success { "✓" } else { "✗" };
+            writeln!(f, "  {} {} hook `{}`: {}", symbol, hook.name, hook.command, hook.message)?;
+            if !hook.success && !hook.output.is_empty() {
+                writeln!(f, "    {}", hook.output.replace('\n', "\n    "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What a rollback undid
+#[derive(Debug)]
+pub struct RollbackReport {
+    /// Undone deployments, newest first
+    pub deployments: Vec<Manifest>,
+    pub files: Vec<Reverted>,
+    pub activation: Vec<ActivationResult>,
 }
 
 /// Theme file generator
 pub struct Generator {
-    renderer: TemplateRenderer,
+    renderer: RwLock<TemplateRenderer>,
+    backends: BackendRegistry,
     config: Config,
+    /// Set in dry-run mode: actions are recorded here instead of performed
+    dry_run: Option<DryRun>,
+    /// Deploy and activate programs even when their files are unchanged
+    force: bool,
+    /// Bumped by `cancel_deploys`; a deploy that sees it change stops early
+    /// Shared with the generators `with_config` builds from this one
+    deploy_epoch: Arc<AtomicUsize>,
+}
+
+/// Programs of a deployment, detected and rendered but not yet deployed
+struct DeployPlan {
+    report: DeployReport,
+    pending: Vec<PendingDeploy>,
+    /// Environment of the deployment's hooks
+    env: Vec<(String, String)>,
+    /// What stops the deployment: skips the remaining programs and, with the
+    /// rollback policy, undoes the deployed ones
+    failed: Option<String>,
+}
+
+impl DeployPlan {
+    /// Skip the programs not deployed yet because a newer deploy replaces this one
+    fn supersede(&mut self) {
+        info!("Deploy superseded, skipping the remaining programs");
+        self.report.superseded = true;
+        for pending in self.pending.drain(..) {
+            let entry = &mut self.report.programs[pending.index];
+            entry.status = DeployStatus::Skipped;
+            entry.message = "superseded".to_string();
+        }
+    }
+}
+
+/// A detected program whose rendered files differ from what is deployed
+struct PendingDeploy {
+    /// Position in the report
+    index: usize,
+    program: ProgramConfig,
+    installed: InstalledProgram,
+    files: Vec<RenderedFile>,
+}
+
+/// What deploying one program did
+struct DeployOutcome {
+    /// Activation result, or the error after the program was rolled back
+    result: std::result::Result<Option<ActivationResult>, String>,
+    files: Vec<FileChange>,
+    journal: Journal,
+    hooks: Vec<HookResult>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TaskState {
+    Running,
+    Finished,
+    /// Reported as timed out; the task undoes its deploy when it finishes
+    Abandoned,
+}
+
+/// A program deploy running on a blocking task
+struct DeployTask {
+    index: usize,
+    program: String,
+    started: tokio::time::Instant,
+    state: Arc<Mutex<TaskState>>,
+    handle: tokio::task::JoinHandle<DeployOutcome>,
+}
+
+impl DeployTask {
+    /// Wait for the deploy, giving up `timeout` after it started
+    /// Returns `None` if it timed out; the running task then undoes itself.
+    async fn join(mut self, timeout: Duration) -> (usize, Option<DeployOutcome>) {
+        let joined = match tokio::time::timeout_at(self.started + timeout, &mut self.handle).await {
+            Ok(joined) => joined,
+            Err(_) => {
+                let abandoned = {
+                    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                    let running = *state == TaskState::Running;
+                    if running {
+                        *state = TaskState::Abandoned;
+                    }
+                    running
+                };
+                if abandoned {
+                    error!("✗ Deploying {} timed out after {}s", self.program, timeout.as_secs());
+                    return (self.index, None);
+                }
+                // Finished just as the timeout hit
+                self.handle.await
+            }
+        };
+
+        let outcome = joined.unwrap_or_else(|e| DeployOutcome {
+            result: Err(format!("deploy task failed: {}", e)),
+            files: Vec::new(),
+            journal: Journal::default(),
+            hooks: Vec::new(),
+        });
+        (self.index, Some(outcome))
+    }
 }
 
 impl Generator {
     /// Create a new generator with the given config
     pub fn new(config: Config) -> Result<Self> {
-        let renderer = TemplateRenderer::new(None)?;
-        Ok(Generator { renderer, config })
+        let embedded = TemplateRenderer::new(None)?
+            .with_strict(config.strict_templates)
+            .with_user_dirs(&config.template_dirs);
+        let renderer = match embedded.reload() {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                error!("Failed to load user templates, using embedded templates only: {:#}", e);
+                embedded
+            }
+        };
+
+        Ok(Generator {
+            renderer: RwLock::new(renderer),
+            backends: BackendRegistry::new(),
+            config,
+            dry_run: None,
+            force: false,
+            deploy_epoch: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Record every write, symlink and command instead of performing it
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run.then(DryRun::new);
+        self
+    }
+
+    /// Redeploy programs whose rendered files match what is deployed
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// A generator for `config` with the same dry-run and force settings
+    /// Cancelling deploys on either one also cancels those of the other.
+    pub fn with_config(&self, config: Config) -> Result<Generator> {
+        Ok(Generator {
+            deploy_epoch: Arc::clone(&self.deploy_epoch),
+            ..Generator::new(config)?
+                .with_dry_run(self.dry_run.is_some())
+                .with_force(self.force)
+        })
+    }
+
+    /// Stop running deploys before their next program: a newer deploy replaces them
+    /// Programs already deploying finish; the rest are reported as skipped.
+    pub fn cancel_deploys(&self) {
+        self.deploy_epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self, epoch: usize) -> bool {
+        self.deploy_epoch.load(Ordering::SeqCst) != epoch
+    }
+
+    /// Actions recorded in dry-run mode
+    pub fn dry_run(&self) -> Option<&DryRun> {
+        self.dry_run.as_ref()
+    }
+
+    fn deploy_context<'a>(&'a self, theme_dir: &'a Path) -> DeployContext<'a> {
+        DeployContext::new(&self.config, theme_dir).with_dry_run(self.dry_run.as_ref())
+    }
+
+    /// Get the configuration this generator was created with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Current template renderer
+    fn renderer(&self) -> RwLockReadGuard<'_, TemplateRenderer> {
+        self.renderer.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Re-parse the user templates after files in the template directories changed
+    /// Returns the names of the enabled programs whose output is affected.
+    /// On a syntax error the previous templates stay active and the error is returned.
+    pub fn reload_templates(&self, changed_paths: &[PathBuf]) -> Result<Vec<String>> {
+        let new_renderer = self.renderer().reload()?;
+
+        let changed: HashSet<String> = changed_paths
+            .iter()
+            .filter_map(|p| new_renderer.template_name_for_path(p))
+            .collect();
+
+        let affected = {
+            let old_renderer = self.renderer();
+            self.config
+                .enabled_programs()
+                .filter(|program| {
+                    self.program_templates(program).iter().any(|template| {
+                        let new_entry = new_renderer.resolve(template).ok();
+                        let old_entry = old_renderer.resolve(template).ok();
+                        new_entry != old_entry
+                            || new_entry.is_some_and(|e| changed.contains(&e.file_name))
+                    })
+                })
+                .map(|program| program.name.clone())
+                .collect()
+        };
+
+        *self.renderer.write().unwrap_or_else(|e| e.into_inner()) = new_renderer;
+        info!("✓ Reloaded templates");
+
+        Ok(affected)
+    }
+
+    /// Templates a program renders during deployment
+    fn program_templates(&self, program: &ProgramConfig) -> Vec<String> {
+        match self.backends.for_program(program) {
+            Some(backend) => backend.templates(program),
+            None => vec![program.template.clone()],
+        }
     }
 
     /// Generate missing theme files for a theme directory
@@ -42,19 +431,199 @@ impl Generator {
 
         info!("Extracted colors from {:?}", source.filename());
 
+        let provenance = Provenance::new(theme_dir, &theme_dir.join(source.filename()), &palette);
         let mut results = Vec::new();
 
         // Check each enabled program
         for program in self.config.enabled_programs() {
-            let result = self.generate_for_program(theme_dir, &palette, program);
+            let result = self.generate_for_program(theme_dir, &palette, &provenance, program, false);
             results.push(result);
         }
 
         Ok(results)
     }
 
+    /// Generate the files of every theme directory in `themes_dir`, several
+    /// themes at a time; with `force` existing files are regenerated too
+    pub fn generate_all_themes(&self, themes_dir: &Path, force: bool) -> Result<Vec<ThemeGeneration>> {
+        let mut theme_dirs: Vec<PathBuf> = fs::read_dir(themes_dir)
+            .with_context(|| format!("Failed to read themes directory {:?}", themes_dir))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        theme_dirs.sort();
+
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4)
+            .min(theme_dirs.len());
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<ThemeGeneration>>> =
+            Mutex::new(theme_dirs.iter().map(|_| None).collect());
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(theme_dir) = theme_dirs.get(index) else {
+                        break;
+                    };
+
+                    let generated = if force {
+                        self.regenerate_all_files(theme_dir)
+                    } else {
+                        self.generate_missing_files(theme_dir)
+                    };
+
+                    results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(ThemeGeneration {
+                        theme_dir: theme_dir.clone(),
+                        results: generated.map_err(|e| format!("{:#}", e)),
+                    });
+                });
+            }
+        });
+
+        Ok(results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// Render a one-off template file with the palette of a theme directory
+    pub fn render_template_file(
+        &self,
+        template_path: &Path,
+        theme_dir: &Path,
+        variables: &std::collections::HashMap<String, String>,
+    ) -> Result<String> {
+        let color_priority = self.parse_color_priority();
+        let (palette, source) = extractor::extract_colors(theme_dir, &color_priority)
+            .with_context(|| format!("Failed to extract colors from {:?}", theme_dir))?;
+
+        info!("Extracted colors from {:?}", source.filename());
+
+        let mut renderer = self.renderer().clone();
+        let template_name = renderer.add_template_file(template_path)?;
+
+        Self::report_missing_slots_for(&template_name, &renderer, &palette);
+
+        let theme_dir = fs::canonicalize(theme_dir).unwrap_or_else(|_| theme_dir.to_path_buf());
+        let provenance = Provenance::new(&theme_dir, &theme_dir.join(source.filename()), &palette);
+
+        renderer.render_with_provenance(&template_name, &palette, variables, &provenance)
+    }
+
     /// Full workflow: detect programs, generate themes, create symlinks, and activate
-    pub fn generate_and_deploy(&self, theme_dir: &Path) -> Result<()> {
+    pub async fn generate_and_deploy(self: &Arc<Self>, theme_dir: &Path) -> Result<DeployReport> {
+        self.deploy_programs(theme_dir, None, self.config.auto_activate, self.force).await
+    }
+
+    /// Regenerate and redeploy the given programs, or every program, even when unchanged
+    pub async fn regenerate(self: &Arc<Self>, theme_dir: &Path, programs: Option<&[String]>) -> Result<DeployReport> {
+        self.deploy_programs(theme_dir, programs.map(<[String]>::to_vec), self.config.auto_activate, true)
+            .await
+    }
+
+    /// Run the deployment workflow for a subset of the enabled programs
+    pub async fn redeploy_programs(self: &Arc<Self>, theme_dir: &Path, programs: &[String]) -> Result<DeployReport> {
+        self.deploy_programs(theme_dir, Some(programs.to_vec()), self.config.auto_activate, self.force)
+            .await
+    }
+
+    /// Generate and put the files in place without activating them
+    pub async fn link_programs(self: &Arc<Self>, theme_dir: &Path) -> Result<DeployReport> {
+        self.deploy_programs(theme_dir, None, false, self.force).await
+    }
+
+    /// Deploy every selected program, undoing the changes of a program that
+    /// fails and, with the rollback policy, of all other programs
+    /// Programs are detected and rendered first, then deployed concurrently on
+    /// blocking tasks, each within `deploy_timeout_secs`. Only a theme without
+    /// usable colors is an error; program failures are in the report.
+    async fn deploy_programs(
+        self: &Arc<Self>,
+        theme_dir: &Path,
+        only: Option<Vec<String>>,
+        activate: bool,
+        force: bool,
+    ) -> Result<DeployReport> {
+        let epoch = self.deploy_epoch.load(Ordering::SeqCst);
+        let generator = Arc::clone(self);
+        let dir = theme_dir.to_path_buf();
+        let mut plan = tokio::task::spawn_blocking(move || generator.plan_deploy(&dir, only.as_deref(), force, epoch))
+            .await
+            .context("Deploy planning panicked")??;
+
+        let env = Arc::new(std::mem::take(&mut plan.env));
+        let timeout = Duration::from_secs(self.config.deploy_timeout_secs);
+        // Dry runs deploy one program at a time so the recorded plan reads in order
+        let batch_size = if self.dry_run.is_some() { 1 } else { plan.pending.len().max(1) };
+
+        let mut journals: Vec<(usize, Journal)> = Vec::new();
+        while !plan.pending.is_empty() {
+            if self.is_cancelled(epoch) {
+                plan.supersede();
+                break;
+            }
+
+            let batch: Vec<PendingDeploy> = plan.pending.drain(..batch_size.min(plan.pending.len())).collect();
+            let tasks: Vec<DeployTask> = batch
+                .into_iter()
+                .map(|pending| self.spawn_deploy(theme_dir, pending, activate, &env, epoch))
+                .collect();
+
+            // Joined in program order, so the report keeps the config order
+            for task in tasks {
+                let (index, outcome) = task.join(timeout).await;
+                let entry = &mut plan.report.programs[index];
+
+                let Some(outcome) = outcome else {
+                    entry.status = DeployStatus::Failed;
+                    entry.message = format!("timed out after {}s, undone once it finishes", timeout.as_secs());
+                    continue;
+                };
+
+                entry.files = outcome.files;
+                entry.hooks = outcome.hooks;
+                match outcome.result {
+                    Ok(activation) => {
+                        entry.message = match &activation {
+                            Some(result) if !result.success => format!("activation failed: {}", result.message),
+                            Some(result) => result.message.clone(),
+                            None => format!("{} files", entry.files.len()),
+                        };
+                        entry.activation = activation;
+                        journals.push((index, outcome.journal));
+                    }
+                    Err(message) => {
+                        entry.status = DeployStatus::Failed;
+                        entry.message = message;
+                    }
+                }
+            }
+        }
+
+        if self.config.on_deploy_failure == FailurePolicy::Rollback && plan.failed.is_none() {
+            plan.failed = plan
+                .report
+                .programs
+                .iter()
+                .find(|p| p.status == DeployStatus::Failed)
+                .map(|p| p.program.clone());
+        }
+
+        let generator = Arc::clone(self);
+        tokio::task::spawn_blocking(move || generator.finish_deploy(plan.report, journals, plan.failed, &env))
+            .await
+            .context("Deploy cleanup panicked")
+    }
+
+    /// Extract the palette, run the global `pre_deploy` hook, and detect and
+    /// render every selected program, leaving those that changed (or, with
+    /// `force`, all of them) to deploy
+    fn plan_deploy(&self, theme_dir: &Path, only: Option<&[String]>, force: bool, epoch: usize) -> Result<DeployPlan> {
         info!("Starting full theme deployment workflow...");
 
         // Extract color palette from theme directory
@@ -64,316 +633,585 @@ impl Generator {
 
         info!("✓ Extracted colors from {:?}", source.filename());
 
+        let provenance = Provenance::new(theme_dir, &theme_dir.join(source.filename()), &palette);
+        let mut plan = DeployPlan {
+            report: DeployReport {
+                theme_dir: theme_dir.to_path_buf(),
+                programs: Vec::new(),
+                hooks: Vec::new(),
+                superseded: false,
+            },
+            pending: Vec::new(),
+            env: self.hook_env(theme_dir, &palette),
+            failed: None,
+        };
+
+        if let Some(command) = &self.config.pre_deploy {
+            let hook = self.deploy_context(theme_dir).run_hook("pre_deploy", command, &plan.env);
+            if !hook.success {
+                plan.failed = Some("pre_deploy hook".to_string());
+            }
+            plan.report.hooks.push(hook);
+        }
+
         // Process each enabled program
         for program_config in self.config.enabled_programs() {
-            // Detect if this program is installed
-            let installed = ProgramDetector::detect_by_config_name(&program_config.name);
+            if only.is_some_and(|names| !names.contains(&program_config.name)) {
+                continue;
+            }
 
-            if installed.is_none() {
-                info!("⊘ {} not installed, skipping", program_config.name);
+            if !plan.report.superseded && self.is_cancelled(epoch) {
+                plan.supersede();
+            }
+
+            if let Some(failed) = &plan.failed {
+                plan.report.programs.push(ProgramReport::new(
+                    program_config,
+                    DeployStatus::Skipped,
+                    format!("{} failed", failed),
+                ));
+                continue;
+            }
+
+            if plan.report.superseded {
+                plan.report
+                    .programs
+                    .push(ProgramReport::new(program_config, DeployStatus::Skipped, "superseded"));
                 continue;
             }
 
-            let installed = installed.unwrap();
+            let Some(backend) = self.backends.for_program(program_config) else {
+                let message = format!(
+                    "Unknown program type: {} (supported: {})",
+                    program_config.backend_name(),
+                    self.backends.names().join(", ")
+                );
+                warn!("{}", message);
+                plan.report
+                    .programs
+                    .push(ProgramReport::new(program_config, DeployStatus::Unsupported, message));
+                continue;
+            };
+
+            // Detect if this program is installed
+            let Some(installed) = backend.detect(program_config) else {
+                info!("⊘ {} not installed, skipping", program_config.name);
+                plan.report
+                    .programs
+                    .push(ProgramReport::new(program_config, DeployStatus::NotInstalled, ""));
+                continue;
+            };
             info!("✓ Detected {}", program_config.name);
 
-            // Handle each program type differently
-            match program_config.name.as_str() {
-                "omarcord" => self.deploy_omarcord(theme_dir, &palette, program_config, &installed)?,
-                "omarchify" => self.deploy_omarchify(theme_dir, &palette, program_config, &installed)?,
-                "omarcava" => self.deploy_omarcava(theme_dir, &palette, program_config, &installed)?,
-                "omarclock" => self.deploy_omarclock(theme_dir, &palette, program_config, &installed)?,
-                "omarvscode" => self.deploy_omarvscode(theme_dir, &palette, program_config, &installed)?,
-                _ => warn!("Unknown program type: {}", program_config.name),
+            let mut entry = ProgramReport {
+                missing_slots: self.report_missing_slots(program_config, &palette),
+                ..ProgramReport::new(program_config, DeployStatus::Deployed, "")
+            };
+
+            match self.render_program(backend, &palette, &provenance, program_config) {
+                Ok(files) if !force && self.is_deployed(backend, program_config, &installed, &files) => {
+                    info!("= {} unchanged, skipping", program_config.name);
+                    entry.status = DeployStatus::Unchanged;
+                }
+                Ok(files) => plan.pending.push(PendingDeploy {
+                    index: plan.report.programs.len(),
+                    program: program_config.clone(),
+                    installed,
+                    files,
+                }),
+                Err(e) => {
+                    error!("✗ Rendering {} failed: {:#}", program_config.name, e);
+                    entry.status = DeployStatus::Failed;
+                    entry.message = format!("{:#}", e);
+
+                    if self.config.on_deploy_failure == FailurePolicy::Rollback {
+                        plan.failed = Some(program_config.name.clone());
+                    }
+                }
+            }
+
+            plan.report.programs.push(entry);
+        }
+
+        // With the rollback policy a render failure stops the whole deployment
+        if let Some(failed) = &plan.failed {
+            for pending in plan.pending.drain(..) {
+                let entry = &mut plan.report.programs[pending.index];
+                entry.status = DeployStatus::Skipped;
+                entry.message = format!("{} failed", failed);
             }
         }
 
-        info!("✓ Theme deployment complete!");
-        Ok(())
+        Ok(plan)
     }
 
-    /// Deploy Omarcord (Discord theme)
-    fn deploy_omarcord(
-        &self,
-        _theme_dir: &Path,
-        palette: &ColorPalette,
-        program_config: &ProgramConfig,
-        installed: &crate::detector::InstalledProgram,
-    ) -> Result<()> {
-        // 1. Render the full omarcord.theme.css template
-        let content = self.renderer.render(&program_config.template, palette, &program_config.variables)
-            .context("Failed to render Omarcord template")?;
-
-        // 2. Save to centralized location (for backup/reference)
-        let output_dir = &self.config.generated_themes_dir;
-        fs::create_dir_all(output_dir)?;
-        let generated_file = output_dir.join(&program_config.output_file);
-        fs::write(&generated_file, &content)?;
-        info!("✓ Generated Omarcord theme: {:?}", generated_file);
-
-        // 3. Write directly to Vencord themes folder (Vencord doesn't support symlinks)
-        let vencord_theme_file = installed.theme_dir.join(&program_config.output_file);
-
-        // Backup existing file if it exists and backups are enabled
-        if vencord_theme_file.exists() && self.config.create_backups {
-            let backup_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".config/omarchy-themes/backups");
-            fs::create_dir_all(&backup_dir)?;
-
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-            let backup_file = backup_dir.join(format!("omarcord.theme.css.{}", timestamp));
-            fs::copy(&vencord_theme_file, &backup_file).ok();
-        }
-
-        // Write the theme file directly
-        fs::write(&vencord_theme_file, content)?;
-        info!("✓ Wrote Omarcord theme to Vencord: {:?}", vencord_theme_file);
-
-        // 4. Activate if enabled
-        if self.config.auto_activate {
-            let result = ThemeActivator::activate_omarcord(installed)?;
-            if result.success {
-                info!("✓ Activated Omarcord: {}", result.message);
-            } else {
-                warn!("✗ Activation failed: {}", result.message);
+    /// Deploy one program on a blocking task
+    fn spawn_deploy(
+        self: &Arc<Self>,
+        theme_dir: &Path,
+        pending: PendingDeploy,
+        activate: bool,
+        env: &Arc<Vec<(String, String)>>,
+        epoch: usize,
+    ) -> DeployTask {
+        let generator = Arc::clone(self);
+        let theme_dir = theme_dir.to_path_buf();
+        let env = Arc::clone(env);
+        let state = Arc::new(Mutex::new(TaskState::Running));
+        let task_state = Arc::clone(&state);
+        let index = pending.index;
+        let program = pending.program.name.clone();
+
+        let handle = tokio::task::spawn_blocking(move || {
+            let outcome = generator.deploy_program(&theme_dir, &pending, activate, &env);
+
+            let mut state = task_state.lock().unwrap_or_else(|e| e.into_inner());
+            if *state == TaskState::Abandoned && outcome.result.is_ok() {
+                // Already reported as timed out: don't leave it half-counted,
+                // unless a newer deploy has taken over and undoing would
+                // bring back files from before it
+                if generator.is_cancelled(epoch) {
+                    info!("{} finished after its timeout, a newer deploy has taken over", pending.program.name);
+                } else {
+                    warn!("↺ Undoing {}, which finished after its timeout", pending.program.name);
+                    if let Err(e) = outcome.journal.rollback_untouched() {
+                        warn!("Rollback of {} incomplete: {:#}", pending.program.name, e);
+                    }
+                }
             }
+            *state = TaskState::Finished;
+            outcome
+        });
+
+        DeployTask {
+            index,
+            program,
+            started: tokio::time::Instant::now(),
+            state,
+            handle,
         }
-
-        Ok(())
     }
 
-    /// Deploy Omarclock (tclock futuristic wrapper)
-    fn deploy_omarclock(
+    /// Render-free part of deploying a program: hooks, generated copy,
+    /// backend deploy and activation; a failed deploy is rolled back
+    fn deploy_program(
         &self,
-        _theme_dir: &Path,
-        palette: &ColorPalette,
-        program_config: &ProgramConfig,
-        installed: &crate::detector::InstalledProgram,
-    ) -> Result<()> {
-        // 1. Render the omarclock.sh wrapper template
-        let content = self.renderer.render(&program_config.template, palette, &program_config.variables)
-            .context("Failed to render Omarclock template")?;
-
-        // 2. Save to centralized location (for backup/reference)
-        let output_dir = &self.config.generated_themes_dir;
-        fs::create_dir_all(output_dir)?;
-        let generated_file = output_dir.join(&program_config.output_file);
-        fs::write(&generated_file, &content)?;
-        info!("✓ Generated Omarclock wrapper: {:?}", generated_file);
-
-        // 3. Get the target wrapper script path (~/.local/bin/omarclock)
-        let wrapper_file = installed.config_file.as_ref()
-            .context("Omarclock wrapper path not set")?;
-
-        // 4. Backup existing wrapper if it exists and backups are enabled
-        if wrapper_file.exists() && self.config.create_backups {
-            let backup_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".config/omarchy-themes/backups");
-            fs::create_dir_all(&backup_dir)?;
-
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-            let backup_file = backup_dir.join(format!("omarclock.{}", timestamp));
-            fs::copy(wrapper_file, &backup_file).ok();
-            info!("✓ Backed up existing Omarclock wrapper: {:?}", backup_file);
-        }
-
-        // 5. Write the wrapper script
-        fs::write(wrapper_file, content)?;
-        info!("✓ Wrote Omarclock wrapper to: {:?}", wrapper_file);
-
-        // 6. Make it executable
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(wrapper_file)?.permissions();
-            perms.set_mode(0o755); // rwxr-xr-x
-            fs::set_permissions(wrapper_file, perms)?;
-            info!("✓ Made Omarclock wrapper executable");
-        }
-
-        info!("✓ Omarclock ready! Run 'omarclock' to launch futuristic clock");
+        theme_dir: &Path,
+        pending: &PendingDeploy,
+        activate: bool,
+        env: &[(String, String)],
+    ) -> DeployOutcome {
+        let program = &pending.program;
+        let journal = Journal::default();
+        let mut hooks = Vec::new();
+
+        let result = {
+            let ctx = self
+                .deploy_context(theme_dir)
+                .with_journal(&journal)
+                .with_program(&program.name);
+            self.run_deploy(&ctx, pending, activate, env, &mut hooks)
+        };
+        let files = journal.changes();
 
-        Ok(())
+        let result = result.map_err(|e| {
+            error!("✗ Deploying {} failed: {:#}", program.name, e);
+            let mut message = format!("{:#}", e);
+            if let Err(e) = journal.rollback() {
+                message.push_str(&format!(" (rollback incomplete: {:#})", e));
+            }
+            message
+        });
+
+        DeployOutcome {
+            result,
+            files,
+            journal,
+            hooks,
+        }
     }
 
-    /// Deploy Omarcava (Cava audio visualizer)
-    fn deploy_omarcava(
+    /// Undo deployed programs if the rollback policy applies, run the global
+    /// `post_deploy` hook and record the deployment
+    fn finish_deploy(
         &self,
-        _theme_dir: &Path,
-        palette: &ColorPalette,
-        program_config: &ProgramConfig,
-        installed: &crate::detector::InstalledProgram,
-    ) -> Result<()> {
-        // 1. Render the omarcava.config template
-        let content = self.renderer.render(&program_config.template, palette, &program_config.variables)
-            .context("Failed to render Omarcava template")?;
-
-        // 2. Save to centralized location (for backup/reference)
-        let output_dir = &self.config.generated_themes_dir;
-        fs::create_dir_all(output_dir)?;
-        let generated_file = output_dir.join(&program_config.output_file);
-        fs::write(&generated_file, &content)?;
-        info!("✓ Generated Omarcava config: {:?}", generated_file);
-
-        // 3. Ensure Cava config directory exists
-        let cava_config_dir = &installed.theme_dir;
-        fs::create_dir_all(cava_config_dir)?;
-
-        // 4. Get the target config file path
-        let cava_config_file = installed.config_file.as_ref()
-            .context("Cava config file path not set")?;
-
-        // 5. Backup existing config if it exists and backups are enabled
-        if cava_config_file.exists() && self.config.create_backups {
-            let backup_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".config/omarchy-themes/backups");
-            fs::create_dir_all(&backup_dir)?;
-
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-            let backup_file = backup_dir.join(format!("cava_config.{}", timestamp));
-            fs::copy(cava_config_file, &backup_file).ok();
-            info!("✓ Backed up existing Cava config: {:?}", backup_file);
-        }
-
-        // 6. Write the config file directly
-        fs::write(cava_config_file, content)?;
-        info!("✓ Wrote Omarcava config to: {:?}", cava_config_file);
-
-        // 7. Activate if enabled (send reload signal to running instances)
-        if self.config.auto_activate {
-            let result = ThemeActivator::activate_omarcava(installed)?;
-            if result.success {
-                info!("✓ {}", result.message);
-            } else {
-                info!("→ {}", result.message);
+        mut report: DeployReport,
+        journals: Vec<(usize, Journal)>,
+        failed: Option<String>,
+        env: &[(String, String)],
+    ) -> DeployReport {
+        if let Some(failed) = &failed {
+            warn!("Rolling back programs deployed alongside {}", failed);
+
+            for (index, journal) in journals.iter().rev() {
+                let entry = &mut report.programs[*index];
+                match journal.rollback() {
+                    Ok(_) => {
+                        entry.status = DeployStatus::RolledBack;
+                        entry.message = format!("{} failed", failed);
+                    }
+                    Err(e) => entry.message = format!("rollback failed: {:#}", e),
+                }
             }
         }
 
-        Ok(())
+        // Only worth running when something actually changed
+        let changed = report.programs.iter().any(|p| p.status == DeployStatus::Deployed);
+        if let (Some(command), true) = (&self.config.post_deploy, changed) {
+            report
+                .hooks
+                .push(self.deploy_context(&report.theme_dir).run_hook("post_deploy", command, env));
+        }
+
+        self.record_deployment(&report);
+        if self.dry_run.is_none() {
+            self.record_deployed_state(&report);
+            if let Err(e) = self.prune_backups() {
+                warn!("Failed to prune backups: {:#}", e);
+            }
+        }
+
+        if report.failed() == 0 {
+            info!("✓ Theme deployment complete!");
+        } else {
+            warn!("Theme deployment finished with {} failed programs", report.failed());
+        }
+
+        report
     }
 
-    /// Deploy Omarvscode (VS Code theme)
-    fn deploy_omarvscode(
-        &self,
-        _theme_dir: &Path,
-        palette: &ColorPalette,
-        program_config: &ProgramConfig,
-        installed: &crate::detector::InstalledProgram,
-    ) -> Result<()> {
-        // 1. Create extension directory structure
-        let ext_dir = &installed.theme_dir;
-        let themes_dir = ext_dir.join("themes");
-        fs::create_dir_all(&themes_dir)?;
-
-        // 2. Render the VS Code theme JSON template
-        let theme_content = self.renderer.render("omarvscode", palette, &program_config.variables)
-            .context("Failed to render VS Code theme template")?;
-
-        // 3. Save to centralized location (for backup/reference)
-        let output_dir = &self.config.generated_themes_dir;
-        fs::create_dir_all(output_dir)?;
-        let generated_file = output_dir.join("omarvscode-color-theme.json");
-        fs::write(&generated_file, &theme_content)?;
-        info!("✓ Generated VS Code theme: {:?}", generated_file);
-
-        // 4. Write theme JSON to extension themes directory
-        let theme_file = themes_dir.join("omarvscode-color-theme.json");
-        if theme_file.exists() && self.config.create_backups {
-            let backup_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".config/omarchy-themes/backups");
-            fs::create_dir_all(&backup_dir)?;
-
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-            let backup_file = backup_dir.join(format!("omarvscode-color-theme.json.{}", timestamp));
-            fs::copy(&theme_file, &backup_file).ok();
-        }
-
-        fs::write(&theme_file, theme_content)?;
-        info!("✓ Wrote VS Code theme to: {:?}", theme_file);
-
-        // 5. Render and write package.json
-        let package_content = self.renderer.render("omarvscode-package", palette, &program_config.variables)
-            .context("Failed to render VS Code package.json template")?;
-
-        let package_file = ext_dir.join("package.json");
-        fs::write(&package_file, package_content)?;
-        info!("✓ Wrote VS Code package.json to: {:?}", package_file);
-
-        info!("✓ VS Code theme ready! Reload VS Code window to see changes (Ctrl+Shift+P > Reload Window)");
-        info!("   Then select theme: Ctrl+Shift+P > Preferences: Color Theme > Omarvscode");
+    /// Environment shared by all hooks of a deployment
+    fn hook_env(&self, theme_dir: &Path, palette: &ColorPalette) -> Vec<(String, String)> {
+        let theme = theme_dir.file_name().unwrap_or(theme_dir.as_os_str());
+        let mut env = vec![
+            ("OMARCHY_THEME".to_string(), theme.to_string_lossy().into_owned()),
+            ("OMARCHY_THEME_DIR".to_string(), theme_dir.display().to_string()),
+            (
+                "OMARCHY_GENERATED_DIR".to_string(),
+                self.config.generated_themes_dir.display().to_string(),
+            ),
+        ];
+        env.extend(hooks::palette_env(palette));
+        env
+    }
 
-        Ok(())
+    /// File with the content hashes of the last deploy of each program
+    fn deployed_state_path(&self) -> PathBuf {
+        self.config.generated_themes_dir.join(".deployed.json")
     }
 
-    /// Deploy Omarchify (Spotify theme)
-    fn deploy_omarchify(
-        &self,
-        theme_dir: &Path,
-        palette: &ColorPalette,
-        program_config: &ProgramConfig,
-        installed: &crate::detector::InstalledProgram,
-    ) -> Result<()> {
-        // 1. Read the base color.ini file from Omarchify repo
-        let base_file_path = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("programming/omarchy-theme-gen/Omarchify/text/color.ini");
-
-        let base_content = if base_file_path.exists() {
-            fs::read_to_string(&base_file_path)
-                .with_context(|| format!("Failed to read base color.ini from {:?}", base_file_path))?
-        } else {
-            warn!("Base color.ini not found at {:?}, using minimal base", base_file_path);
-            String::from("; Omarchify color schemes\n\n")
+    fn record_deployed_state(&self, report: &DeployReport) {
+        let path = self.deployed_state_path();
+        let mut state = DeployedState::load(&path);
+
+        for program in report.programs.iter().filter(|p| p.status == DeployStatus::Deployed) {
+            state.record(&program.program, &program.files);
+        }
+
+        if let Err(e) = state.save(&path) {
+            warn!("Failed to save deployed state: {:#}", e);
+        }
+    }
+
+    /// Save a manifest of the deployed files for `history` and `rollback`
+    fn record_deployment(&self, report: &DeployReport) {
+        if self.dry_run.is_some() {
+            return;
+        }
+
+        let Some(mut manifest) = Manifest::from_report(report, &self.config.generated_themes_dir) else {
+            return;
+        };
+
+        match manifest.save(&self.config.deployments_dir) {
+            Ok(path) => info!("✓ Recorded deployment in {:?}", path),
+            Err(e) => warn!("Failed to record deployment: {:#}", e),
+        }
+    }
+
+    /// Backup store configured in `[backups]`
+    pub fn backup_store(&self) -> BackupStore {
+        BackupStore::new(&self.config.backups.dir)
+    }
+
+    /// Apply the backup retention policy; in dry-run mode only report what would go
+    ///
+    /// A deployment whose backups are gone can no longer be rolled back, so it
+    /// is dropped together with every older one: rollback undoes deployments
+    /// newest first and must not skip one in the middle.
+    pub fn prune_backups(&self) -> Result<PruneReport> {
+        let dry_run = self.dry_run.is_some();
+        let mut report = self.backup_store().prune(&self.config.backups, dry_run)?;
+        if report.removed_objects.is_empty() {
+            return Ok(report);
+        }
+
+        let removed: HashSet<&Path> = report.removed_objects.iter().map(PathBuf::as_path).collect();
+        let manifests = self.deployment_history()?;
+        let Some(newest) = manifests
+            .iter()
+            .rposition(|m| m.backups().any(|b| removed.contains(b)))
+        else {
+            return Ok(report);
         };
 
-        // 2. Render the [Omarchify] section
-        let omarchify_section = self.renderer.render("omarchify-colors", palette, &program_config.variables)
-            .context("Failed to render Omarchify color section")?;
+        for manifest in &manifests[..=newest] {
+            if !dry_run {
+                if let Err(e) = fs::remove_file(manifest.path()) {
+                    warn!("Failed to remove deployment manifest {:?}: {}", manifest.path(), e);
+                    continue;
+                }
+            }
+            report.removed_deployments.push(manifest.path().to_path_buf());
+        }
+        Ok(report)
+    }
 
-        // 3. Combine: base content + new section
-        let combined = format!("{}\n{}", base_content, omarchify_section);
+    /// Recorded deployments, oldest first
+    pub fn deployment_history(&self) -> Result<Vec<Manifest>> {
+        Manifest::load_all(&self.config.deployments_dir)
+    }
 
-        // 4. Save to theme directory
-        let theme_color_file = theme_dir.join(&program_config.output_file);
-        fs::write(&theme_color_file, combined)?;
-        info!("✓ Generated Omarchify color.ini: {:?}", theme_color_file);
+    /// Undo recorded deployments back to `target`, then re-run activation
+    pub fn rollback_deployments(&self, target: &RollbackTarget) -> Result<RollbackReport> {
+        let mut manifests = self.deployment_history()?;
+        let plan = manifest::plan_rollback(&manifests, target)?;
 
-        // 5. Create symlink from Spicetify Themes/text/ to watch_path's color.ini
-        //    This ensures Spicetify always uses the current theme
-        if self.config.auto_symlink {
-            let spicetify_text_dir = installed.theme_dir.join("text");
-            fs::create_dir_all(&spicetify_text_dir)?;
+        let ctx = self.deploy_context(&self.config.watch_path);
+        let mut files = Vec::new();
+        let mut deployments = Vec::new();
 
-            let symlink_target = spicetify_text_dir.join("color.ini");
+        for index in plan {
+            let manifest = &mut manifests[index];
+            info!("↺ Rolling back deployment of {} from {}", manifest.theme, manifest.timestamp);
+            files.extend(manifest.revert(&ctx));
 
-            // Symlink to watch_path (current theme) instead of specific theme_dir
-            // This way, when Omarchy changes themes, Spicetify automatically picks it up
-            let symlink_source = self.config.watch_path.join(&program_config.output_file);
+            if self.dry_run.is_none() {
+                manifest
+                    .mark_rolled_back()
+                    .with_context(|| format!("Failed to update manifest of {}", manifest.timestamp))?;
+            }
+            deployments.push(manifest.clone());
+        }
+
+        Ok(RollbackReport {
+            deployments,
+            files,
+            activation: self.activate_programs(),
+        })
+    }
 
-            // Remove existing symlink or file
-            if symlink_target.exists() || symlink_target.is_symlink() {
-                fs::remove_file(&symlink_target).ok();
+    /// Save, deploy and optionally activate a rendered program, running its hooks
+    fn run_deploy(
+        &self,
+        ctx: &DeployContext,
+        pending: &PendingDeploy,
+        activate: bool,
+        env: &[(String, String)],
+        hooks: &mut Vec<HookResult>,
+    ) -> Result<Option<ActivationResult>> {
+        let PendingDeploy {
+            program,
+            installed,
+            files,
+            ..
+        } = pending;
+        let backend = self
+            .backends
+            .for_program(program)
+            .with_context(|| format!("Unknown program type: {}", program.backend_name()))?;
+
+        let join = |paths: Vec<PathBuf>| {
+            let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            paths.join(":")
+        };
+        let mut env = env.to_vec();
+        env.push(("OMARCHY_PROGRAM".to_string(), program.name.clone()));
+        env.push((
+            "OMARCHY_OUTPUT_FILES".to_string(),
+            join(files.iter().map(|f| self.config.generated_themes_dir.join(&f.file_name)).collect()),
+        ));
+        env.push(("OMARCHY_TARGETS".to_string(), join(backend.targets(program, installed))));
+
+        let mut run_hook = |name: &str, command: &Option<String>| -> Result<()> {
+            let Some(command) = command else {
+                return Ok(());
+            };
+            let hook = ctx.run_hook(&format!("{} {}", program.name, name), command, &env);
+            let failed = (!hook.success).then(|| format!("{} hook {}", name, hook.message));
+            hooks.push(hook);
+            match failed {
+                Some(message) => Err(anyhow::anyhow!(message)),
+                None => Ok(()),
             }
+        };
+
+        run_hook("pre_deploy", &program.pre_deploy)?;
 
-            std::os::unix::fs::symlink(&symlink_source, &symlink_target)?;
-            info!("✓ Symlinked Omarchify: {:?} -> {:?}", symlink_target, symlink_source);
+        // Save to centralized location (for backup/reference)
+        for file in files {
+            let path = ctx.write_generated(file)?;
+            info!("✓ Generated {}: {:?}", program.name, path);
         }
 
-        // 6. Activate if enabled
-        if self.config.auto_activate {
-            let result = ThemeActivator::activate_omarchify(installed)?;
+        backend.deploy(ctx, program, installed, files)?;
+
+        let activation = if activate {
+            let result = backend.activate(ctx, program, installed)?;
             if result.success {
-                info!("✓ Activated Omarchify: {}", result.message);
+                info!("✓ Activated {}: {}", program.name, result.message);
             } else {
-                warn!("✗ Activation failed: {}", result.message);
+                warn!("✗ Activation failed for {}: {}", program.name, result.message);
             }
+            Some(result)
+        } else {
+            None
+        };
+
+        run_hook("post_deploy", &program.post_deploy)?;
+
+        Ok(activation)
+    }
+
+    /// Whether the last deploy of this program rendered the same files and
+    /// everything it wrote is still unmodified
+    fn is_deployed(
+        &self,
+        backend: &dyn ProgramBackend,
+        program: &ProgramConfig,
+        installed: &InstalledProgram,
+        files: &[RenderedFile],
+    ) -> bool {
+        let same_files = files.iter().all(|file| {
+            fs::read_to_string(self.config.generated_themes_dir.join(&file.file_name))
+                .is_ok_and(|deployed| templates::same_render(&deployed, &file.content))
+        });
+
+        same_files
+            && DeployedState::load(&self.deployed_state_path()).is_intact(&program.name)
+            && backend.verify(program, installed).ok
+    }
+
+    fn render_program(
+        &self,
+        backend: &dyn ProgramBackend,
+        palette: &ColorPalette,
+        provenance: &Provenance,
+        program: &ProgramConfig,
+    ) -> Result<Vec<RenderedFile>> {
+        let renderer = self.renderer();
+        let ctx = RenderContext {
+            renderer: &renderer,
+            palette,
+            provenance,
+        };
+
+        backend.render(&ctx, program)
+    }
+
+    /// Activate the deployed theme in every enabled, installed program
+    pub fn activate_programs(&self) -> Vec<ActivationResult> {
+        self.switch_programs(|backend, ctx, program, installed| backend.activate(ctx, program, installed))
+    }
+
+    /// Switch every enabled, installed program back to its own theme
+    pub fn deactivate_programs(&self) -> Vec<ActivationResult> {
+        self.switch_programs(|backend, ctx, program, installed| backend.deactivate(ctx, program, installed))
+    }
+
+    fn switch_programs(
+        &self,
+        switch: impl Fn(&dyn ProgramBackend, &DeployContext, &ProgramConfig, &InstalledProgram) -> Result<ActivationResult>,
+    ) -> Vec<ActivationResult> {
+        let ctx = self.deploy_context(&self.config.watch_path);
+        let mut results = Vec::new();
+
+        for program in self.config.enabled_programs() {
+            let Some(backend) = self.backends.for_program(program) else {
+                warn!("Unknown program type: {}", program.backend_name());
+                continue;
+            };
+            let Some(installed) = backend.detect(program) else {
+                continue;
+            };
+
+            let result = switch(backend, &ctx, program, &installed).unwrap_or_else(|e| ActivationResult {
+                program: installed.name.clone(),
+                success: false,
+                message: format!("Error: {:#}", e),
+            });
+            results.push(ActivationResult {
+                program: program.name.clone(),
+                ..result
+            });
         }
 
-        Ok(())
+        results
+    }
+
+    /// Detection and deployment state of every configured program
+    pub fn program_status(&self) -> Vec<ProgramStatus> {
+        self.config
+            .programs
+            .iter()
+            .map(|program| {
+                let backend = self.backends.for_program(program);
+                let installed = backend.and_then(|b| b.detect(program));
+                let verification = backend
+                    .zip(installed.as_ref())
+                    .map(|(b, installed)| b.verify(program, installed));
+
+                ProgramStatus {
+                    program: program.name.clone(),
+                    enabled: program.enabled,
+                    supported: backend.is_some(),
+                    installed,
+                    verification,
+                }
+            })
+            .collect()
+    }
+
+    /// Warn about palette slots the program's templates will fill with defaults
+    fn report_missing_slots(&self, program: &ProgramConfig, palette: &ColorPalette) -> Vec<String> {
+        let renderer = self.renderer();
+        let mut missing: Vec<String> = self
+            .program_templates(program)
+            .iter()
+            .flat_map(|template| Self::report_missing_slots_for(template, &renderer, palette))
+            .collect();
+
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    fn report_missing_slots_for(
+        template: &str,
+        renderer: &TemplateRenderer,
+        palette: &ColorPalette,
+    ) -> Vec<String> {
+        // Strict rendering computes the missing slots itself and fails on them
+        if renderer.is_strict() {
+            return Vec::new();
+        }
+
+        match renderer.missing_slots(template, palette) {
+            Ok(missing) => {
+                if !missing.is_empty() {
+                    warn!(
+                        "{}: theme is missing palette slots, using template defaults for: {}",
+                        template,
+                        missing.join(", ")
+                    );
+                }
+                missing
+            }
+            Err(e) => {
+                warn!("{}: could not inspect template: {:#}", template, e);
+                Vec::new()
+            }
+        }
     }
 
     /// Generate theme file for a specific program
@@ -381,12 +1219,14 @@ impl Generator {
         &self,
         theme_dir: &Path,
         palette: &ColorPalette,
+        provenance: &Provenance,
         program: &ProgramConfig,
+        overwrite: bool,
     ) -> GenerationResult {
         let output_path = theme_dir.join(&program.output_file);
 
         // Check if file already exists
-        if output_path.exists() {
+        if output_path.exists() && !overwrite {
             info!(
                 "Skipping {} - file already exists: {:?}",
                 program.name, output_path
@@ -395,15 +1235,28 @@ impl Generator {
                 program: program.name.clone(),
                 output_file: output_path,
                 success: true,
+                skipped: true,
                 message: "File already exists (skipped)".to_string(),
+                missing_slots: Vec::new(),
             };
         }
 
-        // Render template
-        match self.renderer.render(&program.template, palette, &program.variables) {
+        let missing_slots = self.report_missing_slots(program, palette);
+
+        // Programs without a backend still get their template rendered
+        let content = match self.backends.for_program(program) {
+            Some(backend) => self
+                .render_program(backend, palette, provenance, program)
+                .and_then(|files| files.into_iter().next().map(|f| f.content).context("Nothing was rendered")),
+            None => self
+                .renderer()
+                .render_with_provenance(&program.template, palette, &program.variables, provenance),
+        };
+
+        match content {
             Ok(content) => {
                 // Write file
-                match fs::write(&output_path, content) {
+                match self.deploy_context(theme_dir).write_file(&output_path, &content) {
                     Ok(_) => {
                         info!(
                             "Generated {} theme file: {:?}",
@@ -413,7 +1266,9 @@ impl Generator {
                             program: program.name.clone(),
                             output_file: output_path,
                             success: true,
+                            skipped: false,
                             message: "Generated successfully".to_string(),
+                            missing_slots,
                         }
                     }
                     Err(e) => {
@@ -425,7 +1280,9 @@ impl Generator {
                             program: program.name.clone(),
                             output_file: output_path,
                             success: false,
+                            skipped: false,
                             message: format!("Write error: {}", e),
+                            missing_slots,
                         }
                     }
                 }
@@ -439,7 +1296,9 @@ impl Generator {
                     program: program.name.clone(),
                     output_file: output_path,
                     success: false,
+                    skipped: false,
                     message: format!("Template error: {}", e),
+                    missing_slots,
                 }
             }
         }
@@ -463,7 +1322,6 @@ impl Generator {
     }
 
     /// Force regenerate all files (even if they exist)
-    #[allow(dead_code)]
     pub fn regenerate_all_files(&self, theme_dir: &Path) -> Result<Vec<GenerationResult>> {
         info!("Regenerating all files for theme: {:?}", theme_dir);
 
@@ -474,20 +1332,12 @@ impl Generator {
 
         info!("Extracted colors from {:?}", source.filename());
 
+        let provenance = Provenance::new(theme_dir, &theme_dir.join(source.filename()), &palette);
         let mut results = Vec::new();
 
-        // Generate for each enabled program (delete existing files first)
+        // Generate for each enabled program, replacing existing files
         for program in self.config.enabled_programs() {
-            let output_path = theme_dir.join(&program.output_file);
-
-            // Delete if exists
-            if output_path.exists() {
-                if let Err(e) = fs::remove_file(&output_path) {
-                    warn!("Failed to delete existing file {:?}: {}", output_path, e);
-                }
-            }
-
-            let result = self.generate_for_program(theme_dir, &palette, program);
+            let result = self.generate_for_program(theme_dir, &palette, &provenance, program, true);
             results.push(result);
         }
 
@@ -498,9 +1348,49 @@ impl Generator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::BackupSettings;
     use std::fs;
     use tempfile::TempDir;
 
+    /// Config deploying `programs` with every path inside `temp_dir`, so tests
+    /// never touch the user's templates, backups or deployment history
+    fn test_config(temp_dir: &Path, programs: Vec<ProgramConfig>) -> Config {
+        Config {
+            programs,
+            watch_path: temp_dir.join("current"),
+            themes_dir: temp_dir.join("themes"),
+            generated_themes_dir: temp_dir.join("generated"),
+            template_dirs: vec![temp_dir.join("templates")],
+            backups: BackupSettings {
+                dir: temp_dir.join("backups"),
+                ..BackupSettings::default()
+            },
+            deployments_dir: temp_dir.join("deployments"),
+            ..Config::default()
+        }
+    }
+
+    /// File program rendering the cava template to `target`
+    fn file_program(name: &str, target: &Path) -> ProgramConfig {
+        ProgramConfig {
+            name: name.to_string(),
+            kind: Some("file".to_string()),
+            enabled: true,
+            output_file: format!("{}.conf", name),
+            template: "omarcava".to_string(),
+            target: Some(target.display().to_string()),
+            ..ProgramConfig::default()
+        }
+    }
+
+    fn write_palette(theme_dir: &Path) {
+        fs::write(
+            theme_dir.join("alacritty.toml"),
+            "[colors.primary]\nbackground = \"#1a1b26\"\nforeground = \"#c0caf5\"\n",
+        )
+        .unwrap();
+    }
+
     #[test]
     fn test_generator() {
         let temp_dir = TempDir::new().unwrap();
@@ -513,13 +1403,16 @@ mod tests {
         )
         .unwrap();
 
-        let config = Config::default();
+        let config = Config {
+            programs: Config::default().programs,
+            ..test_config(temp_dir.path(), Vec::new())
+        };
         let generator = Generator::new(config).unwrap();
 
         let results = generator.generate_missing_files(temp_dir.path()).unwrap();
 
         // Should have results for each program
-        assert!(results.len() > 0);
+        assert!(!results.is_empty());
 
         // Check if files were created
         for result in results {
@@ -528,4 +1421,329 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_generate_all_themes() {
+        let temp_dir = TempDir::new().unwrap();
+        for (theme, background) in [("light", "#eff1f5"), ("dark", "#1a1b26")] {
+            let theme_dir = temp_dir.path().join(theme);
+            fs::create_dir_all(&theme_dir).unwrap();
+            fs::write(
+                theme_dir.join("alacritty.toml"),
+                format!("[colors.primary]\nbackground = \"{}\"\nforeground = \"#4c4f69\"\n", background),
+            )
+            .unwrap();
+        }
+        fs::create_dir_all(temp_dir.path().join("broken")).unwrap();
+
+        let config = Config {
+            programs: Config::default().programs,
+            ..test_config(temp_dir.path(), Vec::new())
+        };
+        let generator = Generator::new(config).unwrap();
+        let themes = generator.generate_all_themes(temp_dir.path(), false).unwrap();
+
+        let names: Vec<_> = themes.iter().map(|t| t.theme_dir.file_name().unwrap()).collect();
+        assert_eq!(names, vec!["broken", "dark", "light"]);
+        assert!(themes[0].results.is_err());
+        assert!(temp_dir.path().join("dark/omarcord.theme.css").exists());
+        assert!(temp_dir.path().join("light/config").exists());
+
+        // Existing files are skipped unless forced
+        let again = generator.generate_all_themes(temp_dir.path(), false).unwrap();
+        assert!(again[1].results.as_ref().unwrap().iter().all(|r| r.skipped));
+        let forced = generator.generate_all_themes(temp_dir.path(), true).unwrap();
+        assert!(forced[1].results.as_ref().unwrap().iter().all(|r| !r.skipped));
+    }
+
+    #[test]
+    fn test_reload_templates_reports_affected_programs() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&template_dir).unwrap();
+
+        let config = Config {
+            programs: Config::default().programs,
+            ..test_config(temp_dir.path(), Vec::new())
+        };
+        let generator = Generator::new(config).unwrap();
+
+        let changed = template_dir.join("omarcava.config");
+        fs::write(&changed, "background = '{{ background }}'").unwrap();
+
+        let affected = generator.reload_templates(std::slice::from_ref(&changed)).unwrap();
+        assert_eq!(affected, vec!["omarcava".to_string()]);
+
+        // Broken templates are reported and the previous set stays active
+        fs::write(&changed, "background = '{{ background'").unwrap();
+        assert!(generator.reload_templates(&[changed]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_deploy_rollback_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let first_target = temp_dir.path().join("first.conf");
+        let second_target = temp_dir.path().join("second.conf");
+        fs::write(&first_target, "previous").unwrap();
+
+        let program = |name: &str, target: &Path, post_deploy: &str| ProgramConfig {
+            post_deploy: Some(post_deploy.to_string()),
+            ..file_program(name, target)
+        };
+        let config = Config {
+            create_backups: false,
+            ..test_config(
+                temp_dir.path(),
+                vec![program("first", &first_target, "true"), program("second", &second_target, "exit 1")],
+            )
+        };
+
+        // By default the failed program is undone and the others stay deployed
+        let generator = Arc::new(Generator::new(config.clone()).unwrap());
+        let report = generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        assert_eq!(report.programs[0].status, DeployStatus::Deployed);
+        assert_eq!(report.programs[1].status, DeployStatus::Failed);
+        assert_eq!(report.failed(), 1);
+        assert_ne!(fs::read_to_string(&first_target).unwrap(), "previous");
+        assert!(!second_target.exists());
+
+        // Only the deployed program is recorded, without the generated copy
+        let history = generator.deployment_history().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].programs[0].program, "first");
+        assert_eq!(history[0].programs[0].files.len(), 1);
+
+        fs::write(&first_target, "previous").unwrap();
+        let generator = Arc::new(
+            Generator::new(Config {
+                on_deploy_failure: FailurePolicy::Rollback,
+                ..config
+            })
+            .unwrap(),
+        );
+        let report = generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        assert_eq!(report.programs[0].status, DeployStatus::RolledBack);
+        assert_eq!(report.programs[0].files[0].path, temp_dir.path().join("generated/first.conf"));
+        assert_eq!(fs::read_to_string(&first_target).unwrap(), "previous");
+        assert!(!second_target.exists());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_hooks() {
+        let temp_dir = TempDir::new().unwrap();
+        let theme_dir = temp_dir.path().join("tokyo-night");
+        fs::create_dir_all(&theme_dir).unwrap();
+        write_palette(&theme_dir);
+
+        let target = temp_dir.path().join("app.conf");
+        let env_file = temp_dir.path().join("env");
+        let marker = temp_dir.path().join("post");
+        let program = ProgramConfig {
+            pre_deploy: Some(format!(
+                "echo $OMARCHY_THEME $OMARCHY_PROGRAM $OMARCHY_COLOR_BACKGROUND $OMARCHY_TARGETS > {}",
+                env_file.display()
+            )),
+            ..file_program("app", &target)
+        };
+        let config = Config {
+            post_deploy: Some(format!("touch {}", marker.display())),
+            create_backups: false,
+            ..test_config(temp_dir.path(), vec![program])
+        };
+
+        let generator = Arc::new(Generator::new(config.clone()).unwrap());
+        let report = generator.generate_and_deploy(&theme_dir).await.unwrap();
+        assert_eq!(report.failed(), 0);
+        assert_eq!(report.programs[0].hooks.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&env_file).unwrap().trim(),
+            format!("tokyo-night app #1a1b26 {}", target.display())
+        );
+        assert!(marker.exists());
+
+        // A failing global pre_deploy hook stops the whole deployment
+        fs::remove_file(&target).unwrap();
+        let generator = Generator::new(Config {
+            pre_deploy: Some("exit 4".to_string()),
+            ..config
+        })
+        .unwrap()
+        .with_force(true);
+        let report = Arc::new(generator).generate_and_deploy(&theme_dir).await.unwrap();
+        assert_eq!(report.programs[0].status, DeployStatus::Skipped);
+        assert_eq!(report.failed(), 1);
+        assert!(!target.exists());
+        assert!(report.to_string().contains("✗ pre_deploy hook `exit 4`"));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_programs_are_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let target = temp_dir.path().join("cava.conf");
+        let marker = temp_dir.path().join("deployed");
+        let program = ProgramConfig {
+            post_deploy: Some(format!("echo x >> {}", marker.display())),
+            ..file_program("cava", &target)
+        };
+        let config = Config {
+            create_backups: false,
+            ..test_config(temp_dir.path(), vec![program])
+        };
+
+        let generator = Arc::new(Generator::new(config.clone()).unwrap());
+        let theme_dir = temp_dir.path();
+        let status = |generator: Arc<Generator>| async move {
+            generator.generate_and_deploy(theme_dir).await.unwrap().programs[0].status
+        };
+
+        assert_eq!(status(generator.clone()).await, DeployStatus::Deployed);
+        assert_eq!(status(generator.clone()).await, DeployStatus::Unchanged);
+        assert_eq!(fs::read_to_string(&marker).unwrap().lines().count(), 1);
+
+        // An edited target is redeployed
+        fs::write(&target, "edited").unwrap();
+        assert_eq!(status(generator.clone()).await, DeployStatus::Deployed);
+
+        let forced = Arc::new(Generator::new(config).unwrap().with_force(true));
+        assert_eq!(status(forced).await, DeployStatus::Deployed);
+        assert_eq!(generator.deployment_history().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_prune_drops_deployments_without_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let target = temp_dir.path().join("cava.conf");
+        let mut config = test_config(temp_dir.path(), vec![file_program("cava", &target)]);
+        config.backups.keep_last = 1;
+        let generator = Arc::new(Generator::new(config).unwrap());
+
+        // The first deploy creates the target, the next two back up an edited one
+        for edit in [None, Some("v1"), Some("v2")] {
+            if let Some(content) = edit {
+                fs::write(&target, content).unwrap();
+            }
+            generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        }
+
+        // Pruning the "v1" backup takes its deployment and the older one along
+        let history = generator.deployment_history().unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].backups().all(Path::exists));
+
+        generator.rollback_deployments(&RollbackTarget::Steps(1)).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "v2");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_deploy_with_timeout() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let program = |name: &str, post_deploy: &str| ProgramConfig {
+            post_deploy: Some(post_deploy.to_string()),
+            ..file_program(name, &temp_dir.path().join(name))
+        };
+        let config = Config {
+            create_backups: false,
+            deploy_timeout_secs: 2,
+            ..test_config(
+                temp_dir.path(),
+                vec![program("a", "sleep 1"), program("b", "sleep 1"), program("slow", "sleep 3")],
+            )
+        };
+
+        let started = std::time::Instant::now();
+        let report = Arc::new(Generator::new(config).unwrap())
+            .generate_and_deploy(temp_dir.path())
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(2900));
+
+        let names: Vec<&str> = report.programs.iter().map(|p| p.program.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "slow"]);
+        assert_eq!(report.programs[0].status, DeployStatus::Deployed);
+        assert_eq!(report.programs[1].status, DeployStatus::Deployed);
+        assert_eq!(report.programs[2].status, DeployStatus::Failed);
+        assert!(report.programs[2].message.starts_with("timed out"));
+
+        // The timed-out deploy undoes itself once it finishes
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(temp_dir.path().join("a").exists());
+        assert!(!temp_dir.path().join("slow").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_late_rollback_keeps_newer_deploy() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let target = temp_dir.path().join("app.conf");
+        let slowed = temp_dir.path().join("slowed");
+        // Only the first deploy outlives the deploy timeout
+        let program = ProgramConfig {
+            post_deploy: Some(format!("test -e {0} || {{ touch {0}; sleep 2; }}", slowed.display())),
+            ..file_program("app", &target)
+        };
+        let config = Config {
+            create_backups: false,
+            deploy_timeout_secs: 1,
+            ..test_config(temp_dir.path(), vec![program])
+        };
+        let generator = Arc::new(Generator::new(config).unwrap());
+
+        let report = generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        assert!(report.programs[0].message.starts_with("timed out"));
+
+        // A second deploy finishes while the first is still running
+        fs::write(
+            temp_dir.path().join("alacritty.toml"),
+            "[colors.primary]\nbackground = \"#1a1b26\"\n[colors.normal]\nmagenta = \"#bb9af7\"\n",
+        )
+        .unwrap();
+        let report = generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        assert_eq!(report.programs[0].status, DeployStatus::Deployed);
+
+        // The first one's late rollback leaves the second one's files alone
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(fs::read_to_string(&target).unwrap().contains("#bb9af7"));
+        assert!(fs::read_to_string(temp_dir.path().join("generated/app.conf")).unwrap().contains("#bb9af7"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_deploy_is_superseded() {
+        let temp_dir = TempDir::new().unwrap();
+        write_palette(temp_dir.path());
+
+        let target = temp_dir.path().join("app.conf");
+        let config = Config {
+            // Keeps the deploy busy long enough to cancel it
+            pre_deploy: Some("sleep 0.5".to_string()),
+            ..test_config(temp_dir.path(), vec![file_program("app", &target)])
+        };
+
+        let generator = Arc::new(Generator::new(config).unwrap());
+        let running = tokio::spawn({
+            let generator = Arc::clone(&generator);
+            let theme_dir = temp_dir.path().to_path_buf();
+            async move { generator.generate_and_deploy(&theme_dir).await }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // A generator built from a reloaded config cancels the same deploys
+        generator.with_config(generator.config().clone()).unwrap().cancel_deploys();
+
+        let report = running.await.unwrap().unwrap();
+        assert!(report.superseded);
+        assert_eq!(report.programs[0].status, DeployStatus::Skipped);
+        assert!(!target.exists());
+
+        // Later deploys aren't affected
+        let report = generator.generate_and_deploy(temp_dir.path()).await.unwrap();
+        assert_eq!(report.programs[0].status, DeployStatus::Deployed);
+    }
 }
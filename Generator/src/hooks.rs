@@ -0,0 +1,204 @@
+use crate::color::ColorPalette;
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Outcome of a `pre_deploy`/`post_deploy` hook
+#[derive(Debug, Clone)]
+pub struct HookResult {
+    /// Which hook ran, e.g. `post_deploy` or `waybar pre_deploy`
+    pub name: String,
+    pub command: String,
+    pub success: bool,
+    /// Exit status, timeout or spawn error
+    pub message: String,
+    /// Captured stdout and stderr
+    pub output: String,
+}
+
+/// Environment describing a palette: `OMARCHY_COLOR_RED=#f7768e`, ...
+pub fn palette_env(palette: &ColorPalette) -> Vec<(String, String)> {
+    let custom = palette.custom.keys().map(String::as_str);
+
+    ColorPalette::SLOTS
+        .iter()
+        .copied()
+        .chain(custom)
+        .filter_map(|name| {
+            let color = palette.get(name)?;
+            Some((format!("OMARCHY_COLOR_{}", env_name(name)), color.hex().to_string()))
+        })
+        .collect()
+}
+
+/// `bright-red` -> `BRIGHT_RED`
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Run a hook through `sh -c`, killing it after `timeout`
+pub fn run_hook(name: &str, command: &str, env: &[(String, String)], cwd: &Path, timeout: Duration) -> HookResult {
+    info!("Running {} hook: {}", name, command);
+
+    let result = |success: bool, message: String, output: String| HookResult {
+        name: name.to_string(),
+        command: command.to_string(),
+        success,
+        message,
+        output,
+    };
+
+    let mut sh = Command::new("sh");
+    sh.args(["-c", command])
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .current_dir(cwd);
+
+    let output = match output_with_timeout(&mut sh, timeout) {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("✗ {} hook: {:#}", name, e);
+            return result(false, format!("{:#}", e), String::new());
+        }
+    };
+
+    let text = [&output.stdout, &output.stderr]
+        .into_iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim_end().to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !text.is_empty() {
+        debug!("{}", text);
+    }
+
+    if output.status.success() {
+        result(true, "ok".to_string(), text)
+    } else {
+        warn!("✗ {} hook exited with {}", name, output.status);
+        result(false, format!("exited with {}", output.status), text)
+    }
+}
+
+/// Run a command with its output captured, killing it and everything it
+/// started once `timeout` passes
+///
+/// The command runs in a process group of its own, so a timeout also reaches
+/// the processes a shell started, not just the shell.
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> Result<Output> {
+    let mut child = command
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start")?;
+
+    // Drain the pipes while waiting so a chatty command can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait().context("Failed to wait")? {
+            Some(status) => break status,
+            None if started.elapsed() >= timeout => {
+                kill_group(&mut child);
+                // Processes that left the group may keep the pipes open; don't wait for them
+                anyhow::bail!("Timed out after {}s", timeout.as_secs());
+            }
+            None => std::thread::sleep(Duration::from_millis(20)),
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buffer).ok();
+        }
+        buffer
+    })
+}
+
+/// Kill the process group a child leads
+fn kill_group(child: &mut Child) {
+    let group = format!("-{}", child.id());
+    let killed = Command::new("kill")
+        .args(["-KILL", "--", &group])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        child.kill().ok();
+    }
+    child.wait().ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_palette_env() {
+        let mut palette = ColorPalette {
+            red: Some(Color::new("#f7768e").unwrap()),
+            ..ColorPalette::default()
+        };
+        palette.custom.insert("accent-2".to_string(), Color::new("#7aa2f7").unwrap());
+
+        let env = palette_env(&palette);
+        assert!(env.contains(&("OMARCHY_COLOR_RED".to_string(), "#f7768e".to_string())));
+        assert!(env.contains(&("OMARCHY_COLOR_ACCENT_2".to_string(), "#7aa2f7".to_string())));
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let temp_dir = TempDir::new().unwrap();
+        let env = vec![("OMARCHY_THEME".to_string(), "nord".to_string())];
+        let timeout = Duration::from_secs(5);
+
+        let ok = run_hook("post_deploy", "echo theme=$OMARCHY_THEME; echo warn >&2", &env, temp_dir.path(), timeout);
+        assert!(ok.success);
+        assert!(ok.output.contains("theme=nord"));
+        assert!(ok.output.contains("warn"));
+
+        let failed = run_hook("post_deploy", "exit 2", &env, temp_dir.path(), timeout);
+        assert!(!failed.success);
+        assert!(failed.message.contains('2'));
+
+        let slow = run_hook("post_deploy", "sleep 5", &env, temp_dir.path(), Duration::from_millis(100));
+        assert!(!slow.success);
+        assert!(slow.message.starts_with("Timed out"));
+    }
+
+    #[test]
+    fn test_timeout_kills_hook_children() {
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join("survived");
+
+        // The shell waits on a subshell that would outlive a kill of `sh` alone
+        let command = format!("(sleep 0.5; touch {}) & wait", marker.display());
+        let hook = run_hook("post_deploy", &command, &[], temp_dir.path(), Duration::from_millis(100));
+        assert!(hook.message.starts_with("Timed out"));
+
+        std::thread::sleep(Duration::from_millis(800));
+        assert!(!marker.exists());
+    }
+}
@@ -1,21 +1,34 @@
 mod activator;
+mod atomic;
+mod backup;
+mod backends;
 mod color;
 mod config;
+mod control;
 mod detector;
+mod dryrun;
 mod extractor;
 mod generator;
+mod hooks;
 mod linker;
+mod manifest;
 mod parser;
+#[cfg(test)]
+mod snapshots;
 mod templates;
+mod transaction;
 mod watcher;
 
 use anyhow::{Context, Result};
 use config::Config;
+use control::{ControlClient, Request, Response};
 use detector::ProgramDetector;
 use generator::Generator;
-use linker::SymlinkManager;
-use activator::ThemeActivator;
-use std::path::PathBuf;
+use manifest::{Outcome, RollbackTarget};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use templates::TemplateRenderer;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 use watcher::ThemeWatcher;
@@ -47,12 +60,18 @@ async fn run() -> Result<()> {
         .with_max_level(log_level)
         .with_target(false)
         .with_thread_ids(false)
+        .with_writer(std::io::stderr)
         .compact()
         .finish();
 
     tracing::subscriber::set_global_default(subscriber)
         .context("Failed to set tracing subscriber")?;
 
+    // Record changes instead of applying them (once, generate, link, activate)
+    let dry_run = args.iter().skip(2).any(|a| a == "--dry-run");
+    // Redeploy programs even when their files are unchanged
+    let force = args.iter().skip(2).any(|a| a == "--force");
+
     // Load configuration
     let config = Config::load_or_create_default()
         .context("Failed to load configuration")?;
@@ -67,36 +86,85 @@ async fn run() -> Result<()> {
         }
         "once" | "run-once" => {
             info!("Running once for current theme...");
-            run_once_mode(&config).await
+            run_once_mode(&config, dry_run, force).await
         }
         "generate" => {
-            if args.len() < 3 {
-                eprintln!("Usage: {} generate <theme_dir>", args[0]);
-                std::process::exit(1);
+            if args.iter().skip(2).any(|a| a == "--all") {
+                return run_generate_all_mode(&config, dry_run, force);
             }
-            let theme_dir = PathBuf::from(&args[2]);
-            run_generate_mode(&config, &theme_dir).await
+            let Some(theme_dir) = args.iter().skip(2).find(|a| !a.starts_with("--")) else {
+                eprintln!("Usage: {} generate <theme_dir> | --all [--force] [--dry-run]", args[0]);
+                std::process::exit(1);
+            };
+            run_generate_mode(&config, &PathBuf::from(theme_dir), dry_run, force).await
         }
+        "render" => {
+            let options = parse_render_args(&args[2..]).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                eprintln!(
+                    "Usage: {} render --template <file> [--theme <dir>] [--var key=value]... [--out <path>]",
+                    args[0]
+                );
+                std::process::exit(1);
+            });
+            run_render_mode(&config, &options)
+        }
+        "templates" => match args.get(2).map(String::as_str) {
+            Some("list") | None => run_templates_list_mode(&config),
+            Some(other) => {
+                eprintln!("Unknown templates command: {}", other);
+                eprintln!("Usage: {} templates list", args[0]);
+                std::process::exit(1);
+            }
+        },
         "detect" => {
             info!("Detecting installed programs...");
             run_detect_mode()
         }
         "link" => {
-            info!("Creating symlinks...");
-            run_link_mode(&config)
+            info!("Deploying theme files...");
+            run_link_mode(&config, dry_run).await
         }
         "unlink" => {
             info!("Removing symlinks...");
-            run_unlink_mode(&config)
+            run_unlink_mode(&config, dry_run)
         }
         "activate" => {
             info!("Activating themes...");
-            run_activate_mode(&config)
+            run_activate_mode(&config, dry_run)
+        }
+        "history" => run_history_mode(&config),
+        "backups" => match args.get(2).map(String::as_str) {
+            Some("list") | None => run_backups_list_mode(&config),
+            Some("prune") => run_backups_prune_mode(&config, dry_run),
+            Some(other) => {
+                eprintln!("Unknown backups command: {}", other);
+                eprintln!("Usage: {} backups [list | prune [--dry-run]]", args[0]);
+                std::process::exit(1);
+            }
+        },
+        "rollback" => {
+            let target = parse_rollback_args(&args[2..]).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                eprintln!("Usage: {} rollback [N | --to <timestamp>] [--dry-run]", args[0]);
+                std::process::exit(1);
+            });
+            run_rollback_mode(&config, &target, dry_run)
         }
         "status" => {
             info!("Checking system status...");
             run_status_mode(&config)
         }
+        "ctl" => {
+            let Some(request) = args.get(2).and_then(|command| Request::parse(command)) else {
+                eprintln!(
+                    "Usage: {} ctl <status | report | regenerate | pause | resume | reload-config | subscribe>",
+                    args[0]
+                );
+                std::process::exit(1);
+            };
+            run_ctl_mode(&config, request).await
+        }
         "init-config" => {
             info!("Initializing default configuration...");
             init_config()
@@ -117,27 +185,91 @@ async fn run_watch_mode(config: &Config) -> Result<()> {
     let generator = Generator::new(config.clone())
         .context("Failed to create generator")?;
 
-    let watcher = ThemeWatcher::new(config.watch_path.clone(), generator);
+    let watcher = ThemeWatcher::new(config.watch_path.clone(), generator)
+        .with_config_path(Config::default_path());
 
     info!("Watching {:?} for theme changes...", config.watch_path);
-    info!("Press Ctrl+C to stop");
+    info!("Press Ctrl+C to stop, send SIGHUP to reload the config or SIGUSR1 to regenerate");
 
     watcher.watch().await
 }
 
-async fn run_once_mode(config: &Config) -> Result<()> {
+async fn run_ctl_mode(config: &Config, request: Request) -> Result<()> {
+    let client = match control::socket_path() {
+        Some(path) => ControlClient::connect(&path).await,
+        None => None,
+    };
+    let Some(mut client) = client else {
+        return run_ctl_fallback(config, request).await;
+    };
+
+    client.send(request).await?;
+    while let Some(response) = client.next().await? {
+        match response {
+            Response::Status(status) => print!("{}", status),
+            Response::Report { report: Some(report) } => print!("{}", report.text),
+            Response::Report { report: None } => println!("No deploy has finished yet"),
+            Response::Accepted { command: Request::Subscribe } => {
+                println!("Subscribed to daemon events, press Ctrl+C to stop");
+            }
+            Response::Accepted { command } => println!("Daemon accepted {}", command),
+            Response::Event(event) => println!("{}", event),
+            Response::Error { message } => anyhow::bail!("Daemon error: {}", message),
+        }
+
+        if request != Request::Subscribe {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// `ctl` without a running daemon: do the work in this process where possible
+async fn run_ctl_fallback(config: &Config, request: Request) -> Result<()> {
+    info!("No daemon is running, handling {} in-process", request);
+
+    match request {
+        Request::Status => run_status_mode(config),
+        Request::Report => run_history_mode(config),
+        Request::Regenerate => run_once_mode(config, false, true).await,
+        Request::ReloadConfig => {
+            let config = Config::load(&Config::default_path())?;
+            config.validate()?;
+            println!("Configuration is valid, it is used by the next run");
+            Ok(())
+        }
+        Request::Pause | Request::Resume | Request::Subscribe => {
+            anyhow::bail!("No daemon is running to {}", request)
+        }
+    }
+}
+
+async fn run_once_mode(config: &Config, dry_run: bool, force: bool) -> Result<()> {
     let generator = Generator::new(config.clone())
-        .context("Failed to create generator")?;
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run)
+        .with_force(force);
 
     let watcher = ThemeWatcher::new(config.watch_path.clone(), generator);
 
-    watcher.run_once().await?;
+    let report = watcher.run_once().await?;
+
+    if dry_run {
+        print_dry_run(&watcher.generator());
+    } else if let Some(report) = &report {
+        println!("\n{}", report);
+    }
+
+    if let Some(report) = report.filter(|r| r.failed() > 0) {
+        anyhow::bail!("{} programs failed to deploy", report.failed());
+    }
 
     info!("Done!");
     Ok(())
 }
 
-async fn run_generate_mode(config: &Config, theme_dir: &PathBuf) -> Result<()> {
+async fn run_generate_mode(config: &Config, theme_dir: &PathBuf, dry_run: bool, force: bool) -> Result<()> {
     if !theme_dir.exists() {
         anyhow::bail!("Theme directory doesn't exist: {:?}", theme_dir);
     }
@@ -145,9 +277,14 @@ async fn run_generate_mode(config: &Config, theme_dir: &PathBuf) -> Result<()> {
     info!("Generating theme files for {:?}", theme_dir);
 
     let generator = Generator::new(config.clone())
-        .context("Failed to create generator")?;
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
 
-    let results = generator.generate_missing_files(theme_dir)?;
+    let results = if force {
+        generator.regenerate_all_files(theme_dir)?
+    } else {
+        generator.generate_missing_files(theme_dir)?
+    };
 
     // Print results
     println!("\nGeneration Results:");
@@ -158,6 +295,166 @@ async fn run_generate_mode(config: &Config, theme_dir: &PathBuf) -> Result<()> {
             "{} [{}] {} - {:?}",
             status, result.program, result.message, result.output_file
         );
+        if !result.missing_slots.is_empty() {
+            println!("    missing palette slots: {}", result.missing_slots.join(", "));
+        }
+    }
+    print_dry_run(&generator);
+
+    Ok(())
+}
+
+fn run_generate_all_mode(config: &Config, dry_run: bool, force: bool) -> Result<()> {
+    info!("Generating theme files for every theme in {:?}", config.themes_dir);
+
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
+
+    let themes = generator.generate_all_themes(&config.themes_dir, force)?;
+    if themes.is_empty() {
+        println!("No themes found in {:?}", config.themes_dir);
+        return Ok(());
+    }
+
+    let width = themes
+        .iter()
+        .map(|t| t.theme_dir.file_name().map_or(0, |n| n.len()))
+        .max()
+        .unwrap_or(0)
+        .max("Theme".len());
+
+    println!("\n{:width$}  Generated  Skipped  Failed", "Theme", width = width);
+    println!("{}", "─".repeat(width + 27));
+
+    let mut failed_themes = 0;
+    for theme in &themes {
+        let name = theme.theme_dir.file_name().unwrap_or_default().to_string_lossy();
+        match &theme.results {
+            Ok(results) => {
+                let skipped = results.iter().filter(|r| r.skipped).count();
+                let failed = results.iter().filter(|r| !r.success).count();
+                let generated = results.len() - skipped - failed;
+                if failed > 0 {
+                    failed_themes += 1;
+                }
+                println!("{:width$}  {:>9}  {:>7}  {:>6}", name, generated, skipped, failed, width = width);
+                for result in results.iter().filter(|r| !r.success) {
+                    println!("    ✗ {}: {}", result.program, result.message);
+                }
+            }
+            Err(e) => {
+                failed_themes += 1;
+                println!("{:width$}  ✗ {}", name, e, width = width);
+            }
+        }
+    }
+    print_dry_run(&generator);
+
+    if failed_themes > 0 {
+        anyhow::bail!("{} of {} themes had errors", failed_themes, themes.len());
+    }
+
+    Ok(())
+}
+
+/// Options for the one-off `render` mode
+struct RenderOptions {
+    template: PathBuf,
+    theme: Option<PathBuf>,
+    variables: HashMap<String, String>,
+    out: Option<PathBuf>,
+}
+
+fn parse_render_args(args: &[String]) -> Result<RenderOptions> {
+    let mut template = None;
+    let mut theme = None;
+    let mut variables = HashMap::new();
+    let mut out = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .cloned()
+                .with_context(|| format!("Missing value for {}", flag))
+        };
+
+        match arg.as_str() {
+            "--template" | "-t" => template = Some(PathBuf::from(value(arg)?)),
+            "--theme" => theme = Some(PathBuf::from(value(arg)?)),
+            "--out" | "-o" => out = Some(PathBuf::from(value(arg)?)),
+            "--var" => {
+                let pair = value(arg)?;
+                let (key, val) = pair
+                    .split_once('=')
+                    .with_context(|| format!("Invalid --var {:?}, expected key=value", pair))?;
+                variables.insert(key.to_string(), val.to_string());
+            }
+            other => anyhow::bail!("Unknown render option: {}", other),
+        }
+    }
+
+    Ok(RenderOptions {
+        template: template.context("Missing --template <file>")?,
+        theme,
+        variables,
+        out,
+    })
+}
+
+fn run_render_mode(config: &Config, options: &RenderOptions) -> Result<()> {
+    // Default to the current Omarchy theme
+    let theme_dir = options.theme.as_deref().unwrap_or(&config.watch_path);
+    if !theme_dir.exists() {
+        anyhow::bail!("Theme directory doesn't exist: {:?}", theme_dir);
+    }
+
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?;
+
+    let content = generator.render_template_file(&options.template, theme_dir, &options.variables)?;
+
+    match &options.out {
+        Some(out) => write_render_output(out, &content)?,
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
+
+fn write_render_output(out: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = out.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {:?}", parent))?;
+    }
+
+    atomic::write_atomic(out, content)?;
+    info!("Wrote {:?}", out);
+    Ok(())
+}
+
+fn run_templates_list_mode(config: &Config) -> Result<()> {
+    let renderer = TemplateRenderer::new(None)?
+        .with_user_dirs(&config.template_dirs)
+        .reload()?;
+
+    println!("{:<20} {:<7} {:<28} SOURCE", "NAME", "FORMAT", "FILE");
+    for entry in renderer.entries() {
+        println!(
+            "{:<20} {:<7} {:<28} {}",
+            entry.name,
+            entry.format.as_str(),
+            entry.file_name,
+            entry.source
+        );
+    }
+
+    println!();
+    println!("User template directories:");
+    for dir in &config.template_dirs {
+        let status = if dir.is_dir() { "✓" } else { "✗ (missing)" };
+        println!("  {} {}", dir.display(), status);
     }
 
     Ok(())
@@ -194,94 +491,259 @@ fn run_detect_mode() -> Result<()> {
     Ok(())
 }
 
-fn run_link_mode(config: &Config) -> Result<()> {
-    let installed = ProgramDetector::get_installed_enabled(&config.programs);
+async fn run_link_mode(config: &Config, dry_run: bool) -> Result<()> {
+    let theme_dir = linker::resolve_link(&config.watch_path)?;
+
+    let generator = Arc::new(
+        Generator::new(config.clone())
+            .context("Failed to create generator")?
+            .with_dry_run(dry_run),
+    );
+
+    println!("Deploying theme files for {:?}...\n", theme_dir);
+    let report = generator.link_programs(&theme_dir).await?;
+
+    if dry_run {
+        print_dry_run(&generator);
+    } else {
+        print!("{}", report);
+    }
+
+    if report.failed() > 0 {
+        anyhow::bail!("{} programs failed to deploy", report.failed());
+    }
+
+    Ok(())
+}
+
+fn run_unlink_mode(config: &Config, dry_run: bool) -> Result<()> {
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
+
+    let results = generator.deactivate_programs();
+
+    if results.is_empty() {
+        println!("No installed programs detected.");
+        return Ok(());
+    }
+
+    println!("Removing symlinks and deactivating themes...\n");
+
+    for result in results {
+        let status = if result.success { "✓" } else { "✗" };
+        println!("{} {}: {}", status, result.program, result.message);
+    }
+    print_dry_run(&generator);
+
+    Ok(())
+}
+
+fn run_activate_mode(config: &Config, dry_run: bool) -> Result<()> {
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
+
+    let results = generator.activate_programs();
 
-    if installed.is_empty() {
+    if results.is_empty() {
         println!("No installed programs detected.");
         return Ok(());
     }
 
-    let linker = SymlinkManager::new(
-        config.generated_themes_dir.clone(),
-        config.create_backups,
-    )?;
+    println!("Activating themes...\n");
 
-    println!("Creating symlinks...\n");
+    for result in results {
+        let status = if result.success { "✓" } else { "✗" };
+        println!("{} {}: {}", status, result.program, result.message);
+    }
+    print_dry_run(&generator);
 
-    for program in &installed {
-        let source_file = if program.name == "spicetify" {
-            "spicetify-omarchy"
-        } else if program.name == "vencord" {
-            "vencord.theme.css"
+    Ok(())
+}
+
+fn run_history_mode(config: &Config) -> Result<()> {
+    let generator = Generator::new(config.clone()).context("Failed to create generator")?;
+    let history = generator.deployment_history()?;
+
+    if history.is_empty() {
+        println!("No deployments recorded in {:?}", config.deployments_dir);
+        return Ok(());
+    }
+
+    println!("Deployments (newest first, N for `rollback N`):\n");
+
+    let mut n = 0;
+    for manifest in history.iter().rev() {
+        let marker = if manifest.rolled_back {
+            "↺".to_string()
         } else {
-            continue;
+            n += 1;
+            (n - 1).to_string()
         };
+        let files: usize = manifest.programs.iter().map(|p| p.files.len()).sum();
+        let programs: Vec<&str> = manifest.programs.iter().map(|p| p.program.as_str()).collect();
 
-        match linker.create_symlink(program, source_file) {
-            Ok(result) => {
-                let status = if result.success { "✓" } else { "✗" };
-                println!("{} {}: {}", status, result.program, result.message);
-            }
-            Err(e) => {
-                println!("✗ {}: Error - {:#}", program.name, e);
-            }
+        println!(
+            "  {:>2}  {}  {:20}  {} files ({}){}",
+            marker,
+            manifest.timestamp,
+            manifest.theme,
+            files,
+            programs.join(", "),
+            if manifest.rolled_back { "  rolled back" } else { "" }
+        );
+    }
+
+    Ok(())
+}
+
+fn run_backups_list_mode(config: &Config) -> Result<()> {
+    let generator = Generator::new(config.clone()).context("Failed to create generator")?;
+    let store = generator.backup_store();
+    store.import_legacy()?;
+    let groups = store.by_program()?;
+
+    if groups.is_empty() {
+        println!("No backups in {:?}", config.backups.dir);
+        return Ok(());
+    }
+
+    let versions: usize = groups.values().map(Vec::len).sum();
+    println!(
+        "Backups in {:?}: {} versions, {} on disk\n",
+        config.backups.dir,
+        versions,
+        format_size(store.disk_usage())
+    );
+
+    for (program, entries) in &groups {
+        println!("{}:", program);
+        for entry in entries.iter().rev() {
+            println!(
+                "  {}  {:>9}  {}  {}",
+                entry.timestamp,
+                format_size(entry.size),
+                entry.object,
+                entry.source.display()
+            );
         }
+        println!();
     }
 
     Ok(())
 }
 
-fn run_unlink_mode(config: &Config) -> Result<()> {
-    let installed = ProgramDetector::get_installed_enabled(&config.programs);
+fn run_backups_prune_mode(config: &Config, dry_run: bool) -> Result<()> {
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
 
-    if installed.is_empty() {
-        println!("No installed programs detected.");
+    let report = generator.prune_backups()?;
+
+    if report.removed.is_empty() {
+        println!("Nothing to prune (keep_last = {}, max_age_days = {:?})", config.backups.keep_last, config.backups.max_age_days);
         return Ok(());
     }
 
-    let linker = SymlinkManager::new(
-        config.generated_themes_dir.clone(),
-        config.create_backups,
-    )?;
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for entry in &report.removed {
+        println!("{} {} {} ({})", verb, entry.program, entry.timestamp, entry.source.display());
+    }
+    for manifest in &report.removed_deployments {
+        println!("{} deployment record {}", verb, manifest.display());
+    }
+    println!(
+        "\n{} {} backups, {} stored files, {} deployment records, {}",
+        verb,
+        report.removed.len(),
+        report.removed_objects.len(),
+        report.removed_deployments.len(),
+        format_size(report.freed_bytes)
+    );
 
-    println!("Removing symlinks...\n");
+    Ok(())
+}
 
-    for program in &installed {
-        match linker.remove_symlink(program) {
-            Ok(result) => {
-                let status = if result.success { "✓" } else { "✗" };
-                println!("{} {}: {}", status, result.program, result.message);
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Parse `rollback` arguments: `[N | --to <timestamp>]`, default 1
+fn parse_rollback_args(args: &[String]) -> Result<RollbackTarget, String> {
+    let mut target = RollbackTarget::Steps(1);
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dry-run" => {}
+            "--to" => {
+                let timestamp = iter.next().ok_or("--to needs a timestamp")?;
+                target = RollbackTarget::To(timestamp.clone());
             }
-            Err(e) => {
-                println!("✗ {}: Error - {:#}", program.name, e);
+            n => {
+                let steps = n.parse().map_err(|_| format!("Invalid rollback count: {}", n))?;
+                target = RollbackTarget::Steps(steps);
             }
         }
     }
 
-    Ok(())
+    Ok(target)
 }
 
-fn run_activate_mode(config: &Config) -> Result<()> {
-    let installed = ProgramDetector::get_installed_enabled(&config.programs);
+fn run_rollback_mode(config: &Config, target: &RollbackTarget, dry_run: bool) -> Result<()> {
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?
+        .with_dry_run(dry_run);
+
+    let report = generator.rollback_deployments(target)?;
 
-    if installed.is_empty() {
-        println!("No installed programs detected.");
-        return Ok(());
+    for manifest in &report.deployments {
+        println!("↺ Rolled back {} ({})", manifest.theme, manifest.timestamp);
     }
+    println!();
 
-    println!("Activating themes...\n");
-
-    let results = ThemeActivator::activate_all(&installed);
+    for file in &report.files {
+        let (status, message) = match &file.outcome {
+            Outcome::Restored(message) => ("✓", message),
+            Outcome::Skipped(message) => ("⊘", message),
+            Outcome::Failed(message) => ("✗", message),
+        };
+        println!("{} {}: {} ({})", status, file.program, file.path.display(), message);
+    }
 
-    for result in results {
-        let status = if result.success { "✓" } else { "✗" };
-        println!("{} {}: {}", status, result.program, result.message);
+    if !report.activation.is_empty() {
+        println!();
+        for result in &report.activation {
+            let status = if result.success { "✓" } else { "✗" };
+            println!("{} {}: {}", status, result.program, result.message);
+        }
+    }
+    print_dry_run(&generator);
+
+    let failed = report
+        .files
+        .iter()
+        .filter(|f| matches!(f.outcome, Outcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{} files could not be restored", failed);
     }
 
     Ok(())
 }
 
+/// Print the planned actions of a dry run
+fn print_dry_run(generator: &Generator) {
+    if let Some(dry_run) = generator.dry_run() {
+        dry_run.print_report();
+    }
+}
+
 fn run_status_mode(config: &Config) -> Result<()> {
     println!("Omarchy Theme Generator - System Status\n");
 
@@ -313,33 +775,35 @@ fn run_status_mode(config: &Config) -> Result<()> {
     println!("  Auto-activate: {}", if config.auto_activate { "✓" } else { "✗" });
     println!("  Auto-symlink: {}", if config.auto_symlink { "✓" } else { "✗" });
     println!("  Create backups: {}", if config.create_backups { "✓" } else { "✗" });
+    println!("  Strict templates: {}", if config.strict_templates { "✓" } else { "✗" });
     println!();
 
-    // Detect installed programs
-    println!("Installed Programs:");
-    let installed = ProgramDetector::detect_all();
-
-    if installed.is_empty() {
-        println!("  None detected");
-    } else {
-        for program in &installed {
-            let enabled = config.programs.iter()
-                .any(|p| p.name == program.name && p.enabled);
+    // Detect and verify configured programs
+    let generator = Generator::new(config.clone())
+        .context("Failed to create generator")?;
+    print_program_status(&generator);
 
-            let status = if enabled { "✓ Enabled" } else { "✗ Disabled" };
-            println!("  {} - {} ({})", program.name, program.theme_dir.display(), status);
-        }
-    }
-    println!();
+    Ok(())
+}
 
-    // Check enabled programs in config
-    println!("Configured Programs:");
-    for program in &config.programs {
-        let status = if program.enabled { "✓" } else { "✗" };
-        println!("  {} {} (template: {})", status, program.name, program.template);
+/// Print detection and deployment state for every configured program
+fn print_program_status(generator: &Generator) {
+    println!("Programs:");
+    for status in generator.program_status() {
+        let enabled = if status.enabled { "✓" } else { "✗" };
+        let state = match (&status.installed, &status.verification) {
+            _ if !status.supported => "unknown program type".to_string(),
+            (None, _) => "not installed".to_string(),
+            (Some(installed), Some(verification)) => format!(
+                "{} {} ({})",
+                if verification.ok { "✓" } else { "✗" },
+                verification.message,
+                installed.theme_dir.display()
+            ),
+            (Some(installed), None) => installed.theme_dir.display().to_string(),
+        };
+        println!("  {} {} - {}", enabled, status.program, state);
     }
-
-    Ok(())
 }
 
 fn init_config() -> Result<()> {
@@ -373,17 +837,38 @@ fn print_help(program_name: &str) {
     println!("    {} [MODE] [OPTIONS]", program_name);
     println!();
     println!("MODES:");
-    println!("    watch           Watch for theme changes and auto-generate (default)");
+    println!("    watch           Watch theme switches and color edits, auto-generate (default)");
     println!("    once            Generate files for current theme and exit");
-    println!("    generate <dir>  Generate files for a specific theme directory");
+    println!("    generate <dir>  Generate missing files for a specific theme directory");
+    println!("    generate --all  Generate missing files for every installed theme, in parallel");
+    println!("    render --template <file> [--theme <dir>] [--var key=value]... [--out <path>]");
+    println!("                    Render one template file against a theme (default: the current");
+    println!("                    one) to stdout, or to --out");
+    println!("    templates list  List available templates and where they come from");
     println!("    detect          Detect installed supported programs");
-    println!("    link            Create symlinks to theme directories");
-    println!("    unlink          Remove symlinks from theme directories");
+    println!("    link            Deploy files for the current theme without activating");
+    println!("    unlink          Switch programs back to their own themes and remove the");
+    println!("                    symlinks a deploy left in their directories");
     println!("    activate        Activate themes in supported programs");
-    println!("    status          Show system and configuration status");
+    println!("    status          Show configuration and per-program deployment status");
+    println!("    history         List recorded deployments");
+    println!("    backups list    List backed-up file versions by program");
+    println!("    backups prune   Drop backups outside the [backups] retention policy");
+    println!("    rollback [N]    Undo the last N deployments (default 1), or all after");
+    println!("                    one with --to <timestamp>, and re-activate");
+    println!("    ctl <command>   Talk to the running watcher: status, report, regenerate,");
+    println!("                    pause, resume, reload-config or subscribe. Without a");
+    println!("                    daemon, status, report and regenerate run in-process");
     println!("    init-config     Create default configuration file");
     println!("    help            Show this help message");
     println!();
+    println!("OPTIONS:");
+    println!("    --dry-run       With once, generate, link, unlink, activate or rollback: print every write (as a");
+    println!("                    unified diff), symlink and command without changing anything.");
+    println!("                    With backups prune: list what would be removed");
+    println!("    --force         With once: rewrite and re-activate programs whose files are unchanged.");
+    println!("                    With generate: regenerate files that already exist");
+    println!();
     println!("ENVIRONMENT:");
     println!("    RUST_LOG        Set log level (trace, debug, info, warn, error)");
     println!();
@@ -392,7 +877,11 @@ fn print_help(program_name: &str) {
     println!("    {} detect              # Check which programs are installed", program_name);
     println!("    {} once                # Generate for current theme", program_name);
     println!("    {} status              # Show system status", program_name);
+    println!("    {} ctl pause           # Hold theme changes in the running watcher", program_name);
+    println!("    {} once --dry-run      # Preview what a theme switch would change", program_name);
+    println!("    {} rollback            # Restore the files from before the last deployment", program_name);
     println!("    {} generate ~/.config/omarchy/themes/catppuccin", program_name);
+    println!("    {} render --template waybar.css --out ~/.config/waybar/colors.css", program_name);
     println!("    RUST_LOG=debug {}     # Run with debug logging", program_name);
     println!();
     println!("SUPPORTED PROGRAMS:");
@@ -0,0 +1,393 @@
+use crate::atomic;
+use crate::backends::DeployContext;
+use crate::backup::{content_hash, TIMESTAMP_FORMAT};
+use crate::generator::{DeployReport, DeployStatus};
+use crate::transaction::{FileChange, Previous};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Files one program's deploy changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramFiles {
+    pub program: String,
+    pub files: Vec<FileChange>,
+}
+
+/// Record of one deployment: enough to put every file back as it was
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub timestamp: String,
+    pub theme: String,
+    pub theme_dir: PathBuf,
+    pub programs: Vec<ProgramFiles>,
+    /// Set once the deployment has been undone by `rollback`
+    #[serde(default)]
+    pub rolled_back: bool,
+    /// File the manifest was loaded from or saved to
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Which deployment `rollback` returns to
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollbackTarget {
+    /// Undo the last N deployments
+    Steps(usize),
+    /// Undo every deployment after the one with this timestamp
+    To(String),
+}
+
+/// What a rollback did with one file
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Restored(String),
+    /// Left as is, e.g. because no backup of the old content exists
+    Skipped(String),
+    Failed(String),
+}
+
+/// A file handled by a rollback
+#[derive(Debug)]
+pub struct Reverted {
+    pub program: String,
+    pub path: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl Manifest {
+    /// Manifest of the programs that were deployed, leaving out the copies in
+    /// `generated_dir`; `None` when nothing was deployed
+    pub fn from_report(report: &DeployReport, generated_dir: &Path) -> Option<Self> {
+        let programs: Vec<ProgramFiles> = report
+            .programs
+            .iter()
+            .filter(|p| p.status == DeployStatus::Deployed)
+            .map(|p| ProgramFiles {
+                program: p.program.clone(),
+                files: p
+                    .files
+                    .iter()
+                    .filter(|f| !f.path.starts_with(generated_dir))
+                    .cloned()
+                    .collect(),
+            })
+            .filter(|p| !p.files.is_empty())
+            .collect();
+
+        if programs.is_empty() {
+            return None;
+        }
+
+        let theme = report.theme_dir.file_name().unwrap_or(report.theme_dir.as_os_str());
+
+        Some(Manifest {
+            timestamp: chrono::Local::now().format(TIMESTAMP_FORMAT).to_string(),
+            theme: theme.to_string_lossy().into_owned(),
+            theme_dir: report.theme_dir.clone(),
+            programs,
+            rolled_back: false,
+            path: PathBuf::new(),
+        })
+    }
+
+    /// Write the manifest as `<timestamp>.json` in `dir`
+    pub fn save(&mut self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {:?}", dir))?;
+
+        // Two deployments within a second get numbered files
+        let mut path = dir.join(format!("{}.json", self.timestamp));
+        let mut n = 1;
+        while path.exists() {
+            n += 1;
+            path = dir.join(format!("{}_{}.json", self.timestamp, n));
+        }
+
+        self.path = path.clone();
+        self.write()?;
+        Ok(path)
+    }
+
+    /// Remember that this deployment was undone
+    pub fn mark_rolled_back(&mut self) -> Result<()> {
+        self.rolled_back = true;
+        self.write()
+    }
+
+    /// Backup copies a rollback of this deployment restores from
+    pub fn backups(&self) -> impl Iterator<Item = &Path> {
+        self.programs
+            .iter()
+            .flat_map(|p| &p.files)
+            .filter_map(|f| f.backup.as_deref())
+    }
+
+    /// File the manifest was loaded from or saved to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        atomic::write_atomic(&self.path, content)
+    }
+
+    /// All manifests in `dir`, oldest first; unreadable ones are skipped
+    pub fn load_all(dir: &Path) -> Result<Vec<Manifest>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut manifests = Vec::new();
+        for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            let loaded = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(serde_json::from_str::<Manifest>(&content)?));
+            match loaded {
+                Ok(manifest) => manifests.push(Manifest { path, ..manifest }),
+                Err(e) => warn!("Skipping unreadable deployment manifest {:?}: {:#}", path, e),
+            }
+        }
+
+        manifests.sort_by_key(|m| (m.timestamp.clone(), m.path.as_os_str().len(), m.path.clone()));
+        Ok(manifests)
+    }
+
+    /// Put back every file this deployment changed, newest change first
+    pub fn revert(&self, ctx: &DeployContext) -> Vec<Reverted> {
+        let mut reverted = Vec::new();
+
+        for program in self.programs.iter().rev() {
+            for file in program.files.iter().rev() {
+                let outcome = match revert_file(ctx, file) {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        warn!("Could not revert {:?}: {:#}", file.path, e);
+                        Outcome::Failed(format!("{:#}", e))
+                    }
+                };
+
+                reverted.push(Reverted {
+                    program: program.program.clone(),
+                    path: file.path.clone(),
+                    outcome,
+                });
+            }
+        }
+
+        reverted
+    }
+}
+
+fn revert_file(ctx: &DeployContext, file: &FileChange) -> Result<Outcome> {
+    match (&file.previous, &file.backup) {
+        (Previous::Missing, _) => {
+            ctx.remove_file(&file.path)?;
+            Ok(Outcome::Restored("removed".to_string()))
+        }
+        (Previous::Symlink(target), _) => {
+            ctx.symlink(target, &file.path)?;
+            Ok(Outcome::Restored(format!("relinked to {}", target.display())))
+        }
+        (Previous::File, Some(backup)) => {
+            let content = fs::read(backup).with_context(|| format!("Failed to read backup {:?}", backup))?;
+            // A symlink to a generated file was put here by the deploy and must
+            // not redirect the restore; a user's own symlink is restored through
+            if ctx.is_generated_link(&file.path) {
+                ctx.remove_file(&file.path)?;
+            }
+            ctx.write_bytes(&file.path, &content)?;
+            if let Some(mode) = file.mode {
+                ctx.set_mode(&file.path, mode)?;
+            }
+            Ok(Outcome::Restored(format!("restored from {}", backup.display())))
+        }
+        (Previous::File, None) => Ok(Outcome::Skipped("no backup was made".to_string())),
+    }
+}
+
+/// Content hashes of the files each program's last deploy left in place
+/// Lets a redeploy tell whether the deployed files were changed since.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeployedState {
+    programs: BTreeMap<String, BTreeMap<PathBuf, String>>,
+}
+
+impl DeployedState {
+    /// Load the state file; a missing or unreadable one is empty
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::write_atomic(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Remember the current content of the files a program's deploy changed
+    pub fn record(&mut self, program: &str, files: &[FileChange]) {
+        let hashes = files
+            .iter()
+            .filter_map(|file| Some((file.path.clone(), content_hash(&fs::read(&file.path).ok()?))))
+            .collect();
+        self.programs.insert(program.to_string(), hashes);
+    }
+
+    /// Whether every file of the program's last deploy still has the content it was given
+    pub fn is_intact(&self, program: &str) -> bool {
+        self.programs.get(program).is_some_and(|files| {
+            files
+                .iter()
+                .all(|(path, hash)| fs::read(path).is_ok_and(|content| &content_hash(&content) == hash))
+        })
+    }
+}
+
+/// Indices of the manifests to undo for `target`, newest first
+/// Deployments that were already rolled back don't count.
+pub fn plan_rollback(manifests: &[Manifest], target: &RollbackTarget) -> Result<Vec<usize>> {
+    let active: Vec<usize> = (0..manifests.len()).filter(|&i| !manifests[i].rolled_back).collect();
+
+    let keep = match target {
+        RollbackTarget::Steps(0) => anyhow::bail!("Nothing to roll back: N must be at least 1"),
+        RollbackTarget::Steps(n) if *n > active.len() => {
+            anyhow::bail!("Only {} deployments can be rolled back", active.len())
+        }
+        RollbackTarget::Steps(n) => active.len() - n,
+        RollbackTarget::To(timestamp) => {
+            let position = active
+                .iter()
+                .rposition(|&i| &manifests[i].timestamp == timestamp)
+                .with_context(|| format!("No deployment at {} (see `history`)", timestamp))?;
+            if position + 1 == active.len() {
+                anyhow::bail!("{} is the current deployment", timestamp);
+            }
+            position + 1
+        }
+    };
+
+    Ok(active[keep..].iter().rev().copied().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn manifest(timestamp: &str, files: Vec<FileChange>) -> Manifest {
+        Manifest {
+            timestamp: timestamp.to_string(),
+            theme: "tokyo-night".to_string(),
+            theme_dir: PathBuf::from("/themes/tokyo-night"),
+            programs: vec![ProgramFiles {
+                program: "omarcava".to_string(),
+                files,
+            }],
+            rolled_back: false,
+            path: PathBuf::new(),
+        }
+    }
+
+    #[test]
+    fn test_save_load_and_revert() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("config");
+        let created = temp_dir.path().join("new.css");
+        let backup = temp_dir.path().join("config.backup");
+        fs::write(&target, "new").unwrap();
+        fs::write(&created, "new").unwrap();
+        // Not valid UTF-8
+        fs::write(&backup, b"old\xff").unwrap();
+
+        let files = vec![
+            FileChange {
+                path: target.clone(),
+                previous: Previous::File,
+                backup: Some(backup),
+                mode: Some(0o755),
+            },
+            FileChange {
+                path: created.clone(),
+                previous: Previous::Missing,
+                backup: None,
+                mode: None,
+            },
+        ];
+        let dir = temp_dir.path().join("deployments");
+        manifest("20250101_120000", files.clone()).save(&dir).unwrap();
+        manifest("20250101_120000", files).save(&dir).unwrap();
+
+        let mut manifests = Manifest::load_all(&dir).unwrap();
+        assert_eq!(manifests.len(), 2);
+        assert!(manifests[1].path.ends_with("20250101_120000_2.json"));
+
+        let config = Config::default();
+        let reverted = manifests[1].revert(&DeployContext::new(&config, temp_dir.path()));
+        assert!(reverted.iter().all(|r| matches!(r.outcome, Outcome::Restored(_))));
+        assert_eq!(fs::read(&target).unwrap(), b"old\xff");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o755);
+        assert!(!created.exists());
+
+        manifests[1].mark_rolled_back().unwrap();
+        assert!(Manifest::load_all(&dir).unwrap()[1].rolled_back);
+    }
+
+    #[test]
+    fn test_revert_through_user_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let dotfile = temp_dir.path().join("dotfiles/cava.conf");
+        let link = temp_dir.path().join("config");
+        let backup = temp_dir.path().join("config.backup");
+        fs::create_dir_all(dotfile.parent().unwrap()).unwrap();
+        fs::write(&dotfile, "new").unwrap();
+        fs::write(&backup, "old").unwrap();
+        std::os::unix::fs::symlink(&dotfile, &link).unwrap();
+
+        let file = FileChange {
+            path: link.clone(),
+            previous: Previous::File,
+            backup: Some(backup),
+            mode: None,
+        };
+        let config = Config {
+            generated_themes_dir: temp_dir.path().join("generated"),
+            ..Config::default()
+        };
+        revert_file(&DeployContext::new(&config, temp_dir.path()), &file).unwrap();
+
+        assert_eq!(fs::read_link(&link).unwrap(), dotfile);
+        assert_eq!(fs::read_to_string(&dotfile).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_plan_rollback() {
+        let mut manifests: Vec<Manifest> = ["20250101_100000", "20250101_110000", "20250101_120000", "20250101_130000"]
+            .iter()
+            .map(|t| manifest(t, Vec::new()))
+            .collect();
+        manifests[3].rolled_back = true;
+
+        assert_eq!(plan_rollback(&manifests, &RollbackTarget::Steps(1)).unwrap(), vec![2]);
+        assert_eq!(plan_rollback(&manifests, &RollbackTarget::Steps(2)).unwrap(), vec![2, 1]);
+        assert!(plan_rollback(&manifests, &RollbackTarget::Steps(4)).is_err());
+
+        let to = RollbackTarget::To("20250101_100000".to_string());
+        assert_eq!(plan_rollback(&manifests, &to).unwrap(), vec![2, 1]);
+        assert!(plan_rollback(&manifests, &RollbackTarget::To("20250101_120000".to_string())).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Golden-file tests for the embedded templates
+//!
+//! Every embedded template is rendered against the reference palettes in
+//! `tests/fixtures/palettes/` and compared with the checked-in output in
+//! `tests/snapshots/<palette>/<template>`. The rendered output is also checked
+//! for syntax errors according to its format.
+//!
+//! To update the snapshots after an intentional template change:
+//!
+//! ```bash
+//! UPDATE_SNAPSHOTS=1 cargo test snapshots
+//! ```
+
+use crate::color::ColorPalette;
+use crate::extractor::{self, ColorSource};
+use crate::templates::{OutputFormat, TemplateRenderer};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Reference palettes, one theme directory each
+const PALETTES: [&str; 4] = ["dark", "light", "btop-only", "catppuccin-latte"];
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn load_palette(name: &str) -> ColorPalette {
+    let theme_dir = manifest_dir().join("tests/fixtures/palettes").join(name);
+    let priority = [ColorSource::Alacritty, ColorSource::CustomJson, ColorSource::Btop];
+
+    extractor::extract_colors(&theme_dir, &priority)
+        .unwrap_or_else(|e| panic!("Failed to load palette {}: {:#}", name, e))
+        .0
+}
+
+/// (palette, template file name, format, rendered output) for every combination
+fn render_all() -> Vec<(String, String, OutputFormat, String)> {
+    let renderer = TemplateRenderer::new(None).unwrap();
+
+    let mut rendered = Vec::new();
+    for palette_name in PALETTES {
+        let palette = load_palette(palette_name);
+        for entry in renderer.entries() {
+            let content = renderer
+                .render(&entry.file_name, &palette, &HashMap::new())
+                .unwrap_or_else(|e| panic!("Failed to render {} with {}: {:#}", entry.file_name, palette_name, e));
+            rendered.push((palette_name.to_string(), entry.file_name.clone(), entry.format, content));
+        }
+    }
+
+    rendered
+}
+
+fn validate(format: OutputFormat, content: &str) -> Result<()> {
+    if content.contains("{{") || content.contains("{%") {
+        anyhow::bail!("Unrendered template tags in output");
+    }
+
+    match format {
+        OutputFormat::Json => {
+            serde_json::from_str::<serde_json::Value>(content).context("Invalid JSON")?;
+        }
+        // cava's config is INI-style with `#` comments
+        OutputFormat::Ini | OutputFormat::HashComment => validate_ini(content)?,
+        OutputFormat::Css => validate_css(content)?,
+        OutputFormat::Shell => validate_shell(content)?,
+        OutputFormat::Plain => {}
+    }
+
+    Ok(())
+}
+
+/// Every line must be blank, a comment, a `[section]` or a `key = value` pair
+fn validate_ini(content: &str) -> Result<()> {
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+
+        let valid = line.is_empty()
+            || line.starts_with(';')
+            || line.starts_with('#')
+            || (line.starts_with('[') && line.ends_with(']') && line.len() > 2)
+            || line.split_once('=').is_some_and(|(key, _)| !key.trim().is_empty());
+
+        if !valid {
+            anyhow::bail!("Invalid INI line {}: {:?}", number + 1, line);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tokenize comments and strings and check that all brackets balance
+fn validate_css(content: &str) -> Result<()> {
+    let mut stack = Vec::new();
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(next) => {
+                            if next == '\n' {
+                                line += 1;
+                            }
+                            prev = next;
+                        }
+                        None => anyhow::bail!("Unterminated comment"),
+                    }
+                }
+            }
+            '"' | '\'' => loop {
+                match chars.next() {
+                    Some('\\') => {
+                        chars.next();
+                    }
+                    Some(next) if next == c => break,
+                    Some('\n') | None => anyhow::bail!("Unterminated string on line {}", line),
+                    Some(_) => {}
+                }
+            },
+            '{' | '(' | '[' => stack.push((c, line)),
+            '}' | ')' | ']' => {
+                let expected = match c {
+                    '}' => '{',
+                    ')' => '(',
+                    _ => '[',
+                };
+                match stack.pop() {
+                    Some((open, _)) if open == expected => {}
+                    _ => anyhow::bail!("Unbalanced {:?} on line {}", c, line),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((open, line)) = stack.pop() {
+        anyhow::bail!("Unclosed {:?} from line {}", open, line);
+    }
+
+    Ok(())
+}
+
+/// Let bash parse the script without running it
+fn validate_shell(content: &str) -> Result<()> {
+    let mut child = Command::new("bash")
+        .arg("-n")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run bash")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open bash stdin")?
+        .write_all(content.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("bash -n failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(())
+}
+
+/// First line where two texts differ, for readable failures
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut number = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => number += 1,
+            (None, None) => return "trailing whitespace differs".to_string(),
+            (e, a) => {
+                return format!(
+                    "line {}:\n  expected: {:?}\n  actual:   {:?}",
+                    number,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+fn snapshot_path(palette: &str, template: &str) -> PathBuf {
+    manifest_dir().join("tests/snapshots").join(palette).join(template)
+}
+
+fn write_snapshot(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_templates_match_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut failures = Vec::new();
+
+    for (palette, template, _, content) in render_all() {
+        let path = snapshot_path(&palette, &template);
+
+        if update {
+            write_snapshot(&path, &content);
+            continue;
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == content => {}
+            Ok(expected) => failures.push(format!(
+                "{}/{} differs at {}",
+                palette,
+                template,
+                first_difference(&expected, &content)
+            )),
+            Err(_) => failures.push(format!("{}/{}: snapshot missing", palette, template)),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Snapshot mismatches (run `UPDATE_SNAPSHOTS=1 cargo test snapshots` to accept):\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_rendered_templates_are_valid() {
+    let mut failures = Vec::new();
+
+    for (palette, template, format, content) in render_all() {
+        if let Err(e) = validate(format, &content) {
+            failures.push(format!("{}/{}: {:#}", palette, template, e));
+        }
+    }
+
+    assert!(failures.is_empty(), "Invalid rendered output:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_validators_reject_broken_output() {
+    assert!(validate_css("body { color: red;").is_err());
+    assert!(validate_css("a { content: 'x; }").is_err());
+    assert!(validate_ini("[section]\nnot a pair\n").is_err());
+    assert!(validate(OutputFormat::Json, "{\"a\": }").is_err());
+    assert!(validate_shell("if true; then\n").is_err());
+    assert!(validate(OutputFormat::Ini, "key = {{ value }}").is_err());
+}
@@ -1,13 +1,289 @@
-use crate::color::ColorPalette;
+use crate::color::{Color, ColorPalette};
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::path::Path;
-use tera::{Tera, Context as TeraContext};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tera::ast::{Expr, ExprVal, Node};
+use tera::{Tera, Template, Context as TeraContext};
 use tracing::info;
 
+/// Output format of a template, derived from its file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Css,
+    Ini,
+    Json,
+    Shell,
+    /// Config files with `#` comments (cava, toml, ...)
+    HashComment,
+    /// Anything else; no comment syntax is known
+    Plain,
+}
+
+impl OutputFormat {
+    /// Determine the output format from a template or output file name
+    pub fn from_file_name(name: &str) -> Self {
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("");
+
+        match extension {
+            "css" => OutputFormat::Css,
+            "ini" => OutputFormat::Ini,
+            "json" => OutputFormat::Json,
+            "sh" | "bash" => OutputFormat::Shell,
+            "config" | "conf" | "toml" | "yaml" | "yml" | "theme" => OutputFormat::HashComment,
+            _ => OutputFormat::Plain,
+        }
+    }
+
+    /// Short name used in listings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Css => "css",
+            OutputFormat::Ini => "ini",
+            OutputFormat::Json => "json",
+            OutputFormat::Shell => "shell",
+            OutputFormat::HashComment => "conf",
+            OutputFormat::Plain => "text",
+        }
+    }
+
+    /// Format lines as a comment block, if the format has comments
+    fn comment(&self, lines: &[String]) -> Option<String> {
+        let block = match self {
+            OutputFormat::Css => format!("/*\n{}\n */\n", lines.iter().map(|l| format!(" * {}", l)).collect::<Vec<_>>().join("\n")),
+            OutputFormat::Ini => lines.iter().map(|l| format!("; {}\n", l)).collect(),
+            OutputFormat::Shell | OutputFormat::HashComment => lines.iter().map(|l| format!("# {}\n", l)).collect(),
+            OutputFormat::Json | OutputFormat::Plain => return None,
+        };
+        Some(block)
+    }
+}
+
+/// Where a rendered file came from
+/// Injected as a comment header and exposed to templates as `provenance`
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    /// Theme name (the theme directory's name)
+    pub theme: String,
+    /// Color source file the palette was extracted from
+    pub source: String,
+    /// Hash of the palette contents, see `ColorPalette::content_hash`
+    pub palette_hash: String,
+    /// Generator version
+    pub version: String,
+    /// Render time
+    pub rendered_at: String,
+}
+
+impl Provenance {
+    /// Describe a render of `palette`, extracted from `source` in `theme_dir`
+    pub fn new(theme_dir: &Path, source: &Path, palette: &ColorPalette) -> Self {
+        Provenance {
+            theme: theme_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            source: source.display().to_string(),
+            palette_hash: palette.content_hash(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            rendered_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S %z").to_string(),
+        }
+    }
+
+    fn header_lines(&self) -> Vec<String> {
+        let values = [&self.theme, &self.source, &self.palette_hash, &self.rendered_at];
+        std::iter::once(format!("{} {}", GENERATED_BY, self.version))
+            .chain(HEADER_FIELDS.iter().zip(values).map(|(field, value)| format!("{}: {}", field, value)))
+            .collect()
+    }
+
+    /// Byte length of the provenance header at the start of `content`
+    /// Zero if the content doesn't start with one
+    pub fn header_len(content: &str, format: OutputFormat) -> usize {
+        let (open, prefix, close) = match format {
+            OutputFormat::Css => ("/*\n", " * ", " */\n"),
+            OutputFormat::Ini => ("", "; ", ""),
+            OutputFormat::Shell | OutputFormat::HashComment => ("", "# ", ""),
+            _ => return 0,
+        };
+        let is_field = |line: &str| {
+            line.strip_prefix(prefix)
+                .and_then(|rest| rest.split_once(": "))
+                .is_some_and(|(field, _)| HEADER_FIELDS.contains(&field))
+        };
+
+        let Some(body) = content.strip_prefix(open) else {
+            return 0;
+        };
+        let mut lines = body.split_inclusive('\n');
+        let len = match lines.next() {
+            Some(first) if first.strip_prefix(prefix).is_some_and(|rest| rest.starts_with(GENERATED_BY)) => {
+                open.len() + first.len() + lines.take_while(|line| is_field(line)).map(str::len).sum::<usize>()
+            }
+            _ => return 0,
+        };
+
+        if content[len..].starts_with(close) {
+            len + close.len()
+        } else {
+            0
+        }
+    }
+
+    /// Byte range of the provenance header `inject_header` put into `content`,
+    /// whatever its format; empty if there is none
+    fn header_range(content: &str) -> Range<usize> {
+        [OutputFormat::Css, OutputFormat::Ini, OutputFormat::Shell]
+            .into_iter()
+            .find_map(|format| {
+                let offset = header_offset(content, format);
+                let start = offset + usize::from(offset > 0 && content[offset..].starts_with('\n'));
+                let len = Self::header_len(&content[start..], format);
+                (len > 0).then_some(start..start + len)
+            })
+            .unwrap_or(0..0)
+    }
+
+    /// Insert the provenance header into rendered content
+    /// Shell shebangs and a leading CSS metadata block (`/** @name ... */`,
+    /// which Vencord requires first) stay at the top.
+    pub fn inject_header(&self, content: &str, format: OutputFormat) -> String {
+        let Some(header) = format.comment(&self.header_lines()) else {
+            return content.to_string();
+        };
+
+        let split_at = header_offset(content, format);
+        let (before, after) = content.split_at(split_at);
+        if split_at == 0 {
+            format!("{}{}", header, after)
+        } else {
+            format!("{}\n{}{}", before.trim_end_matches('\n'), header, after.trim_start_matches('\n'))
+        }
+    }
+}
+
+/// First line of the provenance header, followed by the version
+const GENERATED_BY: &str = "Generated by omarchy-theme-gen";
+
+/// Provenance header fields after the first line, in order
+const HEADER_FIELDS: [&str; 4] = ["Theme", "Source", "Palette hash", "Rendered"];
+
+const RENDERED_MARKER: &str = "Rendered: ";
+
+/// Where `inject_header` puts the header: below a shell shebang or a leading
+/// CSS metadata block
+fn header_offset(content: &str, format: OutputFormat) -> usize {
+    match format {
+        OutputFormat::Shell if content.starts_with("#!") => {
+            content.find('\n').map(|i| i + 1).unwrap_or(content.len())
+        }
+        OutputFormat::Css if content.starts_with("/**") => {
+            content.find("*/").map(|i| i + 2).unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+/// Whether two renders are the same apart from the render time in their
+/// provenance header
+pub fn same_render(a: &str, b: &str) -> bool {
+    fn parts(content: &str) -> (&str, Vec<&str>, &str) {
+        let header = Provenance::header_range(content);
+        let fields = content[header.clone()]
+            .split_inclusive('\n')
+            .filter(|line| !line.trim_start_matches(['/', '*', ';', '#', ' ']).starts_with(RENDERED_MARKER))
+            .collect();
+        (&content[..header.start], fields, &content[header.end..])
+    }
+
+    parts(a) == parts(b)
+}
+
+/// Where a registered template was loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    /// Compiled into the binary
+    Embedded,
+    /// Found in one of the user template directories
+    User(PathBuf),
+    /// Loaded explicitly for a single render
+    File(PathBuf),
+}
+
+impl std::fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateSource::Embedded => write!(f, "embedded"),
+            TemplateSource::User(path) => write!(f, "user: {}", path.display()),
+            TemplateSource::File(path) => write!(f, "file: {}", path.display()),
+        }
+    }
+}
+
+/// A template registered under a logical name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateEntry {
+    /// Logical name programs refer to (e.g. "omarcord")
+    pub name: String,
+    /// Name of the template file (e.g. "omarcord.theme.css")
+    pub file_name: String,
+    /// Format of the rendered output
+    pub format: OutputFormat,
+    /// Where the template was loaded from
+    pub source: TemplateSource,
+}
+
+impl TemplateEntry {
+    /// Register a file, deriving the logical name from everything before the first `.`
+    fn from_file_name(file_name: &str, source: TemplateSource) -> Self {
+        let (dir, base) = match file_name.rsplit_once('/') {
+            Some((dir, base)) => (Some(dir), base),
+            None => (None, file_name),
+        };
+        let stem = base.split('.').next().unwrap_or(base);
+
+        TemplateEntry {
+            name: match dir {
+                Some(dir) => format!("{}/{}", dir, stem),
+                None => stem.to_string(),
+            },
+            file_name: file_name.to_string(),
+            format: OutputFormat::from_file_name(file_name),
+            source,
+        }
+    }
+}
+
+/// Embedded templates: (logical name, file name, contents)
+const EMBEDDED_TEMPLATES: [(&str, &str, &str); 6] = [
+    ("omarcord", "omarcord.theme.css", include_str!("../templates/omarcord.theme.css")),
+    ("omarchify", "omarchify-colors.ini", include_str!("../templates/omarchify-colors.ini")),
+    ("omarcava", "omarcava.config", include_str!("../templates/omarcava.config")),
+    ("omarclock", "omarclock.sh", include_str!("../templates/omarclock.sh")),
+    ("omarvscode", "omarvscode.json", include_str!("../templates/omarvscode.json")),
+    ("omarvscode-package", "omarvscode-package.json", include_str!("../templates/omarvscode-package.json")),
+];
+
 /// Template renderer for generating theme files
+#[derive(Clone)]
 pub struct TemplateRenderer {
     tera: Tera,
+
+    /// Every loaded template, keyed by file name
+    registry: Vec<TemplateEntry>,
+
+    /// Fail rendering instead of falling back to template defaults
+    /// when a palette slot referenced by the template is missing
+    strict: bool,
+
+    /// User template directories overlaid on the embedded templates
+    user_dirs: Vec<PathBuf>,
 }
 
 impl TemplateRenderer {
@@ -15,55 +291,196 @@ impl TemplateRenderer {
     /// If template_dir is provided, loads templates from that directory
     /// Otherwise, uses embedded templates
     pub fn new(template_dir: Option<&Path>) -> Result<Self> {
-        let mut tera = if let Some(dir) = template_dir {
-            let pattern = dir.join("**/*.{ini,css,json,toml}");
-            Tera::new(pattern.to_str().unwrap())
-                .with_context(|| format!("Failed to load templates from {:?}", dir))?
-        } else {
-            // Use embedded templates
-            let mut tera = Tera::default();
-
-            // Load embedded templates
-            tera.add_raw_template(
-                "omarcord.theme.css",
-                include_str!("../templates/omarcord.theme.css"),
-            )?;
-            tera.add_raw_template(
-                "omarchify-colors.ini",
-                include_str!("../templates/omarchify-colors.ini"),
-            )?;
-            tera.add_raw_template(
-                "omarcava.config",
-                include_str!("../templates/omarcava.config"),
-            )?;
-            tera.add_raw_template(
-                "omarclock.sh",
-                include_str!("../templates/omarclock.sh"),
-            )?;
-            tera.add_raw_template(
-                "omarvscode.json",
-                include_str!("../templates/omarvscode.json"),
-            )?;
-            tera.add_raw_template(
-                "omarvscode-package.json",
-                include_str!("../templates/omarvscode-package.json"),
-            )?;
-
-            tera
+        let mut renderer = TemplateRenderer {
+            tera: Tera::default(),
+            registry: Vec::new(),
+            strict: false,
+            user_dirs: Vec::new(),
         };
 
         // Disable autoescape for all templates
-        tera.autoescape_on(vec![]);
+        renderer.tera.autoescape_on(vec![]);
+
+        if let Some(dir) = template_dir {
+            renderer.user_dirs = vec![dir.to_path_buf()];
+            renderer.load_user_templates()?;
+        } else {
+            for (name, file_name, content) in EMBEDDED_TEMPLATES {
+                renderer.tera.add_raw_template(file_name, content)?;
+                renderer.registry.push(TemplateEntry {
+                    name: name.to_string(),
+                    file_name: file_name.to_string(),
+                    format: OutputFormat::from_file_name(file_name),
+                    source: TemplateSource::Embedded,
+                });
+            }
+        }
+
+        Ok(renderer)
+    }
+
+    /// Enable or disable strict mode
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether rendering rejects templates that reference missing palette slots
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Set the user template directories overlaid on the embedded templates
+    /// A user file with the same name as an embedded template replaces it.
+    /// The directories are read by `reload`.
+    pub fn with_user_dirs(mut self, dirs: &[PathBuf]) -> Self {
+        self.user_dirs = dirs.to_vec();
+        self
+    }
+
+    /// Build a fresh renderer from the embedded and user templates
+    /// The current renderer is left untouched, so a template with syntax
+    /// errors can be reported without losing the working set
+    pub fn reload(&self) -> Result<Self> {
+        let mut renderer = TemplateRenderer::new(None)?
+            .with_strict(self.strict)
+            .with_user_dirs(&self.user_dirs);
+
+        renderer.load_user_templates()?;
+        Ok(renderer)
+    }
+
+    fn load_user_templates(&mut self) -> Result<()> {
+        let mut files = Vec::new();
+        for dir in &self.user_dirs {
+            if dir.is_dir() {
+                collect_template_files(dir, dir, &mut files)?;
+            }
+        }
+
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        info!("Loading {} user template(s)", files.len());
+        for (path, name) in &files {
+            self.register(TemplateEntry::from_file_name(name, TemplateSource::User(path.clone())));
+        }
 
-        Ok(TemplateRenderer { tera })
+        let files = files.into_iter().map(|(path, name)| (path, Some(name)));
+        self.tera
+            .add_template_files(files)
+            .context("Failed to load user templates")
     }
 
-    /// Render a template with the given color palette
+    /// Add an entry to the registry, replacing one with the same file name
+    /// The replacement keeps the logical name programs know the template by.
+    fn register(&mut self, mut entry: TemplateEntry) {
+        if let Some(index) = self.registry.iter().position(|e| e.file_name == entry.file_name) {
+            entry.name = self.registry.remove(index).name;
+        }
+        self.registry.push(entry);
+    }
+
+    /// Load a single template file ad hoc, registered under its file name
+    /// Returns the name to render it with
+    pub fn add_template_file(&mut self, path: &Path) -> Result<String> {
+        let name = path
+            .file_name()
+            .with_context(|| format!("Invalid template path: {:?}", path))?
+            .to_string_lossy()
+            .to_string();
+
+        self.tera
+            .add_template_file(path, Some(&name))
+            .with_context(|| format!("Failed to load template {:?}", path))?;
+        self.register(TemplateEntry::from_file_name(&name, TemplateSource::File(path.to_path_buf())));
+
+        Ok(name)
+    }
+
+    /// All registered templates, sorted by logical name
+    pub fn entries(&self) -> Vec<&TemplateEntry> {
+        let mut entries: Vec<&TemplateEntry> = self.registry.iter().collect();
+        entries.sort_by(|a, b| (&a.name, &a.file_name).cmp(&(&b.name, &b.file_name)));
+        entries
+    }
+
+    /// Look up a template by logical name or file name
+    /// Fails with the list of candidates when the name is missing or ambiguous
+    pub fn resolve(&self, template_name: &str) -> Result<&TemplateEntry> {
+        if let Some(entry) = self.registry.iter().find(|e| e.file_name == template_name) {
+            return Ok(entry);
+        }
+
+        let matches: Vec<&TemplateEntry> = self.entries()
+            .into_iter()
+            .filter(|e| e.name == template_name)
+            .collect();
+
+        match matches.as_slice() {
+            [entry] => Ok(entry),
+            [] => {
+                let available: Vec<&str> = self.entries().iter().map(|e| e.name.as_str()).collect();
+                anyhow::bail!(
+                    "Template '{}' not found. Available templates: {}",
+                    template_name,
+                    available.join(", ")
+                )
+            }
+            candidates => {
+                let candidates: Vec<String> = candidates
+                    .iter()
+                    .map(|e| format!("{} ({})", e.file_name, e.source))
+                    .collect();
+                anyhow::bail!(
+                    "Template name '{}' is ambiguous, use one of the file names instead: {}",
+                    template_name,
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Template name a file inside one of the user directories is loaded as
+    pub fn template_name_for_path(&self, path: &Path) -> Option<String> {
+        self.user_dirs.iter().find_map(|dir| {
+            let relative = path.strip_prefix(dir).ok()?;
+            is_template_file(path).then(|| relative.to_string_lossy().to_string())
+        })
+    }
+
+    /// Render a template with the given color palette, without a provenance header
+    #[cfg(test)]
     pub fn render(
         &self,
         template_name: &str,
         palette: &ColorPalette,
         extra_vars: &HashMap<String, String>,
+    ) -> Result<String> {
+        self.render_inner(template_name, palette, extra_vars, None)
+    }
+
+    /// Render a template and prefix it with a provenance header
+    /// in the comment syntax of the template's output format
+    pub fn render_with_provenance(
+        &self,
+        template_name: &str,
+        palette: &ColorPalette,
+        extra_vars: &HashMap<String, String>,
+        provenance: &Provenance,
+    ) -> Result<String> {
+        let content = self.render_inner(template_name, palette, extra_vars, Some(provenance))?;
+        let format = self.resolve(template_name)?.format;
+        Ok(provenance.inject_header(&content, format))
+    }
+
+    fn render_inner(
+        &self,
+        template_name: &str,
+        palette: &ColorPalette,
+        extra_vars: &HashMap<String, String>,
+        provenance: Option<&Provenance>,
     ) -> Result<String> {
         let mut context = TeraContext::new();
 
@@ -103,6 +520,14 @@ impl TemplateRenderer {
         // Add custom colors
         for (key, color) in &palette.custom {
             context.insert(key, color.hex());
+            context.insert(format!("{}_hex", key), color.hex_no_hash());
+        }
+
+        // Add the structured view (palette.red.rgb, palette.ansi, ...)
+        context.insert("palette", &palette_object(palette)?);
+
+        if let Some(provenance) = provenance {
+            context.insert("provenance", provenance);
         }
 
         // Add extra variables
@@ -110,40 +535,70 @@ impl TemplateRenderer {
             context.insert(key, value);
         }
 
-        // Determine the full template name (add extension if needed)
-        let full_template_name = if template_name.contains('.') {
-            template_name.to_string()
-        } else {
-            // Try common extensions in priority order
-            // .theme.css comes before -colors.css for themes like omarcord
-            if self.tera.get_template_names().any(|n| n == format!("{}.theme.css", template_name)) {
-                format!("{}.theme.css", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}.config", template_name)) {
-                format!("{}.config", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}.sh", template_name)) {
-                format!("{}.sh", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}.json", template_name)) {
-                format!("{}.json", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}.ini", template_name)) {
-                format!("{}.ini", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}-colors.ini", template_name)) {
-                format!("{}-colors.ini", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}-colors.css", template_name)) {
-                format!("{}-colors.css", template_name)
-            } else if self.tera.get_template_names().any(|n| n == format!("{}.css", template_name)) {
-                format!("{}.css", template_name)
-            } else {
-                template_name.to_string()
+        let full_template_name = &self.resolve(template_name)?.file_name;
+
+        if self.strict {
+            let missing = self.missing_slots(full_template_name, palette)?;
+            if !missing.is_empty() {
+                anyhow::bail!(
+                    "Template {} references palette slots missing from the theme: {}",
+                    full_template_name,
+                    missing.join(", ")
+                );
             }
-        };
+        }
 
         info!("Rendering template: {}", full_template_name);
 
         self.tera
-            .render(&full_template_name, &context)
+            .render(full_template_name, &context)
             .with_context(|| format!("Failed to render template: {}", full_template_name))
     }
 
+    /// Statically collect the variables a template references, following its
+    /// includes and the templates it extends
+    /// Loop variables and `set` targets are excluded
+    pub fn referenced_variables(&self, template_name: &str) -> Result<BTreeSet<String>> {
+        let full_template_name = &self.resolve(template_name)?.file_name;
+        let template = self
+            .tera
+            .get_template(full_template_name)
+            .with_context(|| format!("Template not found: {}", full_template_name))?;
+
+        let mut collector = VarCollector {
+            tera: &self.tera,
+            vars: BTreeSet::new(),
+            locals: BTreeSet::new(),
+            seen: BTreeSet::new(),
+        };
+        collector.template(template);
+
+        let VarCollector { mut vars, locals, .. } = collector;
+        vars.retain(|v| !locals.contains(root_name(v)));
+        Ok(vars)
+    }
+
+    /// List the palette slots a template references that the palette doesn't provide
+    /// These are the places where the template falls back to its literal defaults
+    pub fn missing_slots(&self, template_name: &str, palette: &ColorPalette) -> Result<Vec<String>> {
+        let mut missing = BTreeSet::new();
+
+        for var in self.referenced_variables(template_name)? {
+            // palette.red.hex refers to the `red` slot
+            let name = match var.strip_prefix("palette.") {
+                Some(rest) => root_name(rest),
+                None => root_name(&var),
+            };
+            let slot = name.strip_suffix("_hex").unwrap_or(name);
+
+            if ColorPalette::SLOTS.contains(&slot) && palette.get(slot).is_none() {
+                missing.insert(slot.to_string());
+            }
+        }
+
+        Ok(missing.into_iter().collect())
+    }
+
     /// Get list of available template names
     #[allow(dead_code)]
     pub fn available_templates(&self) -> Vec<String> {
@@ -151,11 +606,296 @@ impl TemplateRenderer {
     }
 }
 
+/// Structured view of a color exposed to templates as `palette.<name>`
+#[derive(Debug, Serialize)]
+struct ColorValue {
+    name: String,
+    /// ANSI index (0-15) for the normal and bright colors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<usize>,
+    hex: String,
+    hex_no_hash: String,
+    rgb: String,
+    r: u8,
+    g: u8,
+    b: u8,
+    hsl: String,
+    h: i64,
+    s: i64,
+    l: i64,
+    luminance: f64,
+}
+
+impl ColorValue {
+    fn new(name: &str, index: Option<usize>, color: &Color) -> Result<Self> {
+        let (r, g, b) = color.to_rgb()?;
+        let (h, s, l) = color.to_hsl()?;
+        let (h, s, l) = (h.round() as i64, (s * 100.0).round() as i64, (l * 100.0).round() as i64);
+
+        Ok(ColorValue {
+            name: name.to_string(),
+            index,
+            hex: color.hex().to_string(),
+            hex_no_hash: color.hex_no_hash().to_string(),
+            rgb: color.to_rgb_string()?,
+            r,
+            g,
+            b,
+            hsl: format!("hsl({}, {}%, {}%)", h, s, l),
+            h,
+            s,
+            l,
+            luminance: (color.luminance()? * 10000.0).round() / 10000.0,
+        })
+    }
+}
+
+/// Build the `palette` template object
+///
+/// Every standard and custom color is available by name, and the ANSI colors
+/// are also grouped into `normal` (0-7), `bright` (8-15) and `ansi` (0-15)
+/// arrays. Missing slots are left out, so loops only see defined colors.
+fn palette_object(palette: &ColorPalette) -> Result<Value> {
+    let mut object = Map::new();
+
+    for name in ColorPalette::SLOTS {
+        if let Some(color) = palette.get(name) {
+            object.insert(name.to_string(), serde_json::to_value(ColorValue::new(name, None, color)?)?);
+        }
+    }
+
+    for (name, color) in &palette.custom {
+        if !object.contains_key(name) {
+            object.insert(name.clone(), serde_json::to_value(ColorValue::new(name, None, color)?)?);
+        }
+    }
+
+    // SLOTS lists black..white then bright_black..bright_white from index 2
+    let mut normal = Vec::new();
+    let mut bright = Vec::new();
+    for (index, name) in ColorPalette::SLOTS[2..18].iter().enumerate() {
+        if let Some(color) = palette.get(name) {
+            let value = serde_json::to_value(ColorValue::new(name, Some(index), color)?)?;
+            if index < 8 {
+                normal.push(value);
+            } else {
+                bright.push(value);
+            }
+        }
+    }
+
+    let ansi: Vec<Value> = normal.iter().chain(bright.iter()).cloned().collect();
+    object.insert("normal".to_string(), Value::Array(normal));
+    object.insert("bright".to_string(), Value::Array(bright));
+    object.insert("ansi".to_string(), Value::Array(ansi));
+
+    Ok(Value::Object(object))
+}
+
+/// Whether a file should be loaded as a template (skips hidden and editor backup files)
+fn is_template_file(path: &Path) -> bool {
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => !name.starts_with('.') && !name.ends_with('~') && !name.ends_with(".swp"),
+        None => false,
+    }
+}
+
+/// Recursively collect (path, template name) pairs below a user template directory
+fn collect_template_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(PathBuf, String)>,
+) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read template directory: {:?}", dir))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_template_files(root, &path, files)?;
+        } else if is_template_file(&path) {
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            files.push((path, name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Root of a dotted/indexed identifier (`palette.red.hex` -> `palette`)
+fn root_name(ident: &str) -> &str {
+    ident.split(['.', '[']).next().unwrap_or(ident)
+}
+
+/// Walks the nodes a template renders, including its includes and parents
+struct VarCollector<'a> {
+    tera: &'a Tera,
+    vars: BTreeSet<String>,
+    locals: BTreeSet<String>,
+    /// Templates already walked, so include cycles end
+    seen: BTreeSet<String>,
+}
+
+impl<'a> VarCollector<'a> {
+    fn template(&mut self, template: &'a Template) {
+        if !self.seen.insert(template.name.clone()) {
+            return;
+        }
+
+        // An extending template renders its top-most parent with its own
+        // blocks filled in
+        let chain = std::iter::once(&template.name)
+            .chain(&template.parents)
+            .filter_map(|name| self.tera.get_template(name).ok());
+        let mut root = template;
+        for ancestor in chain {
+            for macro_def in ancestor.macros.values() {
+                self.locals.extend(macro_def.args.keys().cloned());
+                self.nodes(&macro_def.body, ancestor);
+            }
+            root = ancestor;
+        }
+
+        self.nodes(&root.ast, template);
+    }
+
+    fn nodes(&mut self, nodes: &'a [Node], template: &'a Template) {
+        for node in nodes {
+            match node {
+                Node::VariableBlock(_, expr) => collect_expr(expr, &mut self.vars),
+                Node::Set(_, set) => {
+                    self.locals.insert(set.key.clone());
+                    collect_expr(&set.value, &mut self.vars);
+                }
+                Node::FilterSection(_, section, _) => {
+                    for arg in section.filter.args.values() {
+                        collect_expr(arg, &mut self.vars);
+                    }
+                    self.nodes(&section.body, template);
+                }
+                Node::Block(_, block, _) => {
+                    // The most derived definition renders, the ones it overrides
+                    // only through super()
+                    let tera = self.tera;
+                    let definitions = std::iter::once(&template.name)
+                        .chain(&template.parents)
+                        .filter_map(|name| tera.get_template(name).ok())
+                        .find_map(|t| t.blocks_definitions.get(&block.name));
+                    match definitions {
+                        Some(definitions) => {
+                            for (_, definition) in definitions {
+                                self.nodes(&definition.body, template);
+                                if !definition.body.iter().any(|n| matches!(n, Node::Super)) {
+                                    break;
+                                }
+                            }
+                        }
+                        None => self.nodes(&block.body, template),
+                    }
+                }
+                Node::Include(_, names, _) => {
+                    if let Some(included) = names.iter().find_map(|name| self.tera.get_template(name).ok()) {
+                        self.template(included);
+                    }
+                }
+                Node::Forloop(_, forloop, _) => {
+                    self.locals.insert(forloop.value.clone());
+                    if let Some(key) = &forloop.key {
+                        self.locals.insert(key.clone());
+                    }
+                    self.locals.insert("loop".to_string());
+                    collect_expr(&forloop.container, &mut self.vars);
+                    self.nodes(&forloop.body, template);
+                    if let Some(empty_body) = &forloop.empty_body {
+                        self.nodes(empty_body, template);
+                    }
+                }
+                Node::If(if_node, _) => {
+                    for (_, condition, body) in &if_node.conditions {
+                        collect_expr(condition, &mut self.vars);
+                        self.nodes(body, template);
+                    }
+                    if let Some((_, body)) = &if_node.otherwise {
+                        self.nodes(body, template);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn collect_expr(expr: &Expr, vars: &mut BTreeSet<String>) {
+    collect_val(&expr.val, vars);
+    for filter in &expr.filters {
+        for arg in filter.args.values() {
+            collect_expr(arg, vars);
+        }
+    }
+}
+
+fn collect_val(val: &ExprVal, vars: &mut BTreeSet<String>) {
+    match val {
+        ExprVal::Ident(ident) => {
+            vars.insert(ident.clone());
+        }
+        ExprVal::Math(math) => {
+            collect_expr(&math.lhs, vars);
+            collect_expr(&math.rhs, vars);
+        }
+        ExprVal::Logic(logic) => {
+            collect_expr(&logic.lhs, vars);
+            collect_expr(&logic.rhs, vars);
+        }
+        ExprVal::In(in_expr) => {
+            collect_expr(&in_expr.lhs, vars);
+            collect_expr(&in_expr.rhs, vars);
+        }
+        ExprVal::Test(test) => {
+            vars.insert(test.ident.clone());
+            for arg in &test.args {
+                collect_expr(arg, vars);
+            }
+        }
+        ExprVal::FunctionCall(call) => {
+            for arg in call.args.values() {
+                collect_expr(arg, vars);
+            }
+        }
+        ExprVal::MacroCall(call) => {
+            for arg in call.args.values() {
+                collect_expr(arg, vars);
+            }
+        }
+        ExprVal::Array(items) => {
+            for item in items {
+                collect_expr(item, vars);
+            }
+        }
+        ExprVal::StringConcat(concat) => {
+            for value in &concat.values {
+                collect_val(value, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::color::Color;
 
+    fn add_raw_template(renderer: &mut TemplateRenderer, name: &str, content: &str) -> Result<()> {
+        renderer.tera.add_raw_template(name, content)?;
+        renderer.register(TemplateEntry::from_file_name(name, TemplateSource::Embedded));
+        Ok(())
+    }
+
     #[test]
     fn test_template_renderer() {
         let renderer = TemplateRenderer::new(None).unwrap();
@@ -170,13 +910,15 @@ mod tests {
     #[test]
     fn test_render_omarchify() {
         let renderer = TemplateRenderer::new(None).unwrap();
-        let mut palette = ColorPalette::default();
-        palette.background = Some(Color::new("#eff1f5").unwrap());
-        palette.foreground = Some(Color::new("#4c4f69").unwrap());
-        palette.bright_green = Some(Color::new("#40a02b").unwrap());
-        palette.green = Some(Color::new("#40a02b").unwrap());
+        let palette = ColorPalette {
+            background: Some(Color::new("#eff1f5").unwrap()),
+            foreground: Some(Color::new("#4c4f69").unwrap()),
+            bright_green: Some(Color::new("#40a02b").unwrap()),
+            green: Some(Color::new("#40a02b").unwrap()),
+            ..Default::default()
+        };
 
-        let result = renderer.render("omarchify-colors", &palette, &HashMap::new()).unwrap();
+        let result = renderer.render("omarchify", &palette, &HashMap::new()).unwrap();
 
         assert!(result.contains("[Omarchify]"));
         assert!(result.contains("accent"));
@@ -186,12 +928,14 @@ mod tests {
     #[test]
     fn test_render_omarcord() {
         let renderer = TemplateRenderer::new(None).unwrap();
-        let mut palette = ColorPalette::default();
-        palette.background = Some(Color::new("#1a1b26").unwrap());
-        palette.foreground = Some(Color::new("#c0caf5").unwrap());
-        palette.blue = Some(Color::new("#7aa2f7").unwrap());
-        palette.red = Some(Color::new("#f7768e").unwrap());
-        palette.green = Some(Color::new("#9ece6a").unwrap());
+        let palette = ColorPalette {
+            background: Some(Color::new("#1a1b26").unwrap()),
+            foreground: Some(Color::new("#c0caf5").unwrap()),
+            blue: Some(Color::new("#7aa2f7").unwrap()),
+            red: Some(Color::new("#f7768e").unwrap()),
+            green: Some(Color::new("#9ece6a").unwrap()),
+            ..Default::default()
+        };
 
         let result = renderer.render("omarcord", &palette, &HashMap::new()).unwrap();
 
@@ -206,13 +950,15 @@ mod tests {
     #[test]
     fn test_render_omarcava() {
         let renderer = TemplateRenderer::new(None).unwrap();
-        let mut palette = ColorPalette::default();
-        palette.background = Some(Color::new("#000000").unwrap());
-        palette.foreground = Some(Color::new("#ffffff").unwrap());
-        palette.bright_magenta = Some(Color::new("#bd00ff").unwrap());
-        palette.magenta = Some(Color::new("#d600ff").unwrap());
-        palette.bright_green = Some(Color::new("#00ff9f").unwrap());
-        palette.cyan = Some(Color::new("#00d4ff").unwrap());
+        let palette = ColorPalette {
+            background: Some(Color::new("#000000").unwrap()),
+            foreground: Some(Color::new("#ffffff").unwrap()),
+            bright_magenta: Some(Color::new("#bd00ff").unwrap()),
+            magenta: Some(Color::new("#d600ff").unwrap()),
+            bright_green: Some(Color::new("#00ff9f").unwrap()),
+            cyan: Some(Color::new("#00d4ff").unwrap()),
+            ..Default::default()
+        };
 
         let result = renderer.render("omarcava", &palette, &HashMap::new()).unwrap();
 
@@ -227,6 +973,289 @@ mod tests {
         assert!(result.contains("'#00ff9f'")); // bright_green (primary accent)
         assert!(result.contains("background = '#000000'")); // background
         assert!(result.contains("framerate = 60"));
-        assert!(result.contains("bars = 48"));
+        assert!(result.contains("bars = 0"));
+    }
+
+    #[test]
+    fn test_structured_palette() {
+        let mut renderer = TemplateRenderer::new(None).unwrap();
+        add_raw_template(
+            &mut renderer,
+            "structured.txt",
+            "{{ palette.red.hex }} {{ palette.red.rgb }} {{ palette.red.hsl }} {{ palette.red.luminance }}\n\
+             {% for c in palette.ansi %}{{ c.index }}={{ c.hex_no_hash }} {% endfor %}\n\
+             {{ palette.title.r }} {{ title_hex }}",
+        )
+        .unwrap();
+
+        let mut palette = ColorPalette {
+            red: Some(Color::new("#ff0000").unwrap()),
+            blue: Some(Color::new("#0000ff").unwrap()),
+            bright_white: Some(Color::new("#ffffff").unwrap()),
+            ..Default::default()
+        };
+        palette.custom.insert("title".to_string(), Color::new("#dc8a78").unwrap());
+
+        let result = renderer.render("structured.txt", &palette, &HashMap::new()).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+
+        assert_eq!(lines[0], "#ff0000 255, 0, 0 hsl(0, 100%, 50%) 0.2126");
+        assert_eq!(lines[1], "1=ff0000 4=0000ff 15=ffffff ");
+        assert_eq!(lines[2], "220 dc8a78");
+    }
+
+    #[test]
+    fn test_user_templates_override_and_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_dir = temp_dir.path().to_path_buf();
+        fs::write(user_dir.join("omarclock.sh"), "COLOR={{ red }}").unwrap();
+        fs::write(user_dir.join("omarclock.sh~"), "{{ broken").unwrap();
+
+        let renderer = TemplateRenderer::new(None)
+            .unwrap()
+            .with_user_dirs(std::slice::from_ref(&user_dir))
+            .reload()
+            .unwrap();
+        let palette = ColorPalette {
+            red: Some(Color::new("#ff0000").unwrap()),
+            ..Default::default()
+        };
+
+        assert_eq!(renderer.render("omarclock", &palette, &HashMap::new()).unwrap(), "COLOR=#ff0000");
+        assert_eq!(
+            renderer.template_name_for_path(&user_dir.join("omarclock.sh")),
+            Some("omarclock.sh".to_string())
+        );
+
+        // A syntax error surfaces from reload, leaving the old renderer usable
+        fs::write(user_dir.join("omarclock.sh"), "COLOR={{ red").unwrap();
+        assert!(renderer.reload().is_err());
+        assert!(renderer.render("omarclock", &palette, &HashMap::new()).is_ok());
+    }
+
+    fn test_provenance() -> Provenance {
+        Provenance {
+            theme: "tokyo-night".to_string(),
+            source: "/themes/tokyo-night/alacritty.toml".to_string(),
+            palette_hash: "0123456789abcdef".to_string(),
+            version: "0.1.0".to_string(),
+            rendered_at: "2025-01-01 12:00:00 +0000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_provenance_header_per_format() {
+        let renderer = TemplateRenderer::new(None).unwrap();
+        let palette = ColorPalette::default();
+        let provenance = test_provenance();
+
+        let sh = renderer.render_with_provenance("omarclock", &palette, &HashMap::new(), &provenance).unwrap();
+        assert!(sh.starts_with("#!/bin/bash\n# Generated by omarchy-theme-gen 0.1.0\n"));
+        assert!(sh.contains("# Theme: tokyo-night\n"));
+
+        let css = renderer.render_with_provenance("omarcord", &palette, &HashMap::new(), &provenance).unwrap();
+        assert!(css.starts_with("/**\n * @name Omarcord"));
+        assert!(css.contains(" * Palette hash: 0123456789abcdef\n"));
+
+        let ini = renderer.render_with_provenance("omarchify", &palette, &HashMap::new(), &provenance).unwrap();
+        assert!(ini.starts_with("; Generated by omarchy-theme-gen"));
+        assert!(ini.contains("; Palette hash: 0123456789abcdef\n"));
+
+        // A later render of the same palette only differs in its render time
+        let later = Provenance {
+            rendered_at: "2030-01-01 00:00:00 +0000".to_string(),
+            ..provenance.clone()
+        };
+        let ini_later = renderer.render_with_provenance("omarchify", &palette, &HashMap::new(), &later).unwrap();
+        assert_ne!(ini, ini_later);
+        assert!(same_render(&ini, &ini_later));
+        assert!(!same_render(&ini, &ini_later.replace("Theme: tokyo-night", "Theme: nord")));
+
+        // JSON has no comments, so it is left as is
+        let json = renderer.render_with_provenance("omarvscode", &palette, &HashMap::new(), &provenance).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+        assert!(!json.contains("Palette hash"));
+    }
+
+    #[test]
+    fn test_same_render_only_ignores_header_render_time() {
+        let renderer = TemplateRenderer::new(None).unwrap();
+        let palette = ColorPalette::default();
+        let provenance = test_provenance();
+        let later = Provenance {
+            rendered_at: "2030-01-01 00:00:00 +0000".to_string(),
+            ..provenance.clone()
+        };
+
+        for template in ["omarclock", "omarcord", "omarcava"] {
+            let render = |p: &Provenance| renderer.render_with_provenance(template, &palette, &HashMap::new(), p).unwrap();
+            assert!(same_render(&render(&provenance), &render(&later)), "{}", template);
+        }
+
+        // A changed "Rendered: " line outside the header is a real change
+        let ini = "; Generated by omarchy-theme-gen 0.1.0\n; Rendered: now\n[Omarchify]\n; Rendered: once\n";
+        assert!(same_render(ini, &ini.replacen("now", "later", 1)));
+        assert!(!same_render(ini, &ini.replace("once", "twice")));
+    }
+
+    #[test]
+    fn test_provenance_template_variables() {
+        let mut renderer = TemplateRenderer::new(None).unwrap();
+        add_raw_template(&mut renderer, "provenance.txt", "{{ provenance.theme }} {{ provenance.palette_hash }}")
+            .unwrap();
+
+        let result = renderer
+            .render_with_provenance("provenance.txt", &ColorPalette::default(), &HashMap::new(), &test_provenance())
+            .unwrap();
+        assert_eq!(result, "tokyo-night 0123456789abcdef");
+    }
+
+    #[test]
+    fn test_add_template_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("waybar.css");
+        fs::write(&path, "@define-color bg {{ background }};\n@define-color accent {{ accent }};").unwrap();
+
+        let mut renderer = TemplateRenderer::new(None).unwrap();
+        let name = renderer.add_template_file(&path).unwrap();
+        assert_eq!(name, "waybar.css");
+
+        let palette = ColorPalette {
+            background: Some(Color::new("#1a1b26").unwrap()),
+            ..Default::default()
+        };
+        let vars = HashMap::from([("accent".to_string(), "#ff0000".to_string())]);
+        let result = renderer.render(&name, &palette, &vars).unwrap();
+
+        assert_eq!(result, "@define-color bg #1a1b26;\n@define-color accent #ff0000;");
+    }
+
+    #[test]
+    fn test_registry_resolution() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_dir = temp_dir.path().to_path_buf();
+        fs::write(user_dir.join("omarcava.config"), "bars = {{ bars }}").unwrap();
+        fs::write(user_dir.join("foo.json"), "{}").unwrap();
+        fs::write(user_dir.join("foo.ini"), "[foo]").unwrap();
+
+        let renderer = TemplateRenderer::new(None)
+            .unwrap()
+            .with_user_dirs(std::slice::from_ref(&user_dir))
+            .reload()
+            .unwrap();
+
+        // Same file name replaces the embedded template
+        let entry = renderer.resolve("omarcava").unwrap();
+        assert_eq!(entry.source, TemplateSource::User(user_dir.join("omarcava.config")));
+        assert_eq!(entry.format, OutputFormat::HashComment);
+
+        // The logical name of the embedded omarchify template differs from its file name
+        assert_eq!(renderer.resolve("omarchify").unwrap().file_name, "omarchify-colors.ini");
+        assert_eq!(renderer.resolve("omarchify-colors.ini").unwrap().name, "omarchify");
+
+        // Same logical name from two files is ambiguous, the file name disambiguates
+        let err = format!("{:#}", renderer.resolve("foo").unwrap_err());
+        assert!(err.contains("ambiguous") && err.contains("foo.json") && err.contains("foo.ini"));
+        assert_eq!(renderer.resolve("foo.ini").unwrap().format, OutputFormat::Ini);
+
+        let err = format!("{:#}", renderer.resolve("bar").unwrap_err());
+        assert!(err.contains("not found") && err.contains("omarcord"));
+    }
+
+    #[test]
+    fn test_user_override_keeps_logical_name() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_dir = temp_dir.path().to_path_buf();
+        for (_, file_name, _) in EMBEDDED_TEMPLATES {
+            fs::write(user_dir.join(file_name), format!("override of {}", file_name)).unwrap();
+        }
+
+        let renderer = TemplateRenderer::new(None)
+            .unwrap()
+            .with_user_dirs(std::slice::from_ref(&user_dir))
+            .reload()
+            .unwrap();
+
+        for (name, file_name, _) in EMBEDDED_TEMPLATES {
+            let entry = renderer.resolve(name).unwrap();
+            assert_eq!(entry.file_name, file_name);
+            assert_eq!(entry.source, TemplateSource::User(user_dir.join(file_name)));
+
+            let rendered = renderer.render(name, &ColorPalette::default(), &HashMap::new()).unwrap();
+            assert_eq!(rendered, format!("override of {}", file_name));
+        }
+        assert_eq!(renderer.entries().len(), EMBEDDED_TEMPLATES.len());
+    }
+
+    #[test]
+    fn test_referenced_variables() {
+        let renderer = TemplateRenderer::new(None).unwrap();
+        let vars = renderer.referenced_variables("omarcava").unwrap();
+
+        assert!(vars.contains("bright_magenta"));
+        assert!(vars.contains("background"));
+        assert!(vars.contains("bars"));
+    }
+
+    #[test]
+    fn test_missing_slots() {
+        let renderer = TemplateRenderer::new(None).unwrap();
+        let palette = ColorPalette {
+            background: Some(Color::new("#000000").unwrap()),
+            foreground: Some(Color::new("#ffffff").unwrap()),
+            ..Default::default()
+        };
+
+        let missing = renderer.missing_slots("omarchify", &palette).unwrap();
+
+        assert!(missing.contains(&"bright_cyan".to_string()));
+        assert!(!missing.contains(&"background".to_string()));
+        // Non-palette variables with defaults are not reported
+        let missing = renderer.missing_slots("omarcava", &palette).unwrap();
+        assert!(!missing.contains(&"bars".to_string()));
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_missing_slots() {
+        let renderer = TemplateRenderer::new(None).unwrap().with_strict(true);
+        let palette = ColorPalette {
+            background: Some(Color::new("#000000").unwrap()),
+            ..Default::default()
+        };
+
+        let err = renderer.render("omarclock", &palette, &HashMap::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("bright_cyan"));
+    }
+
+    #[test]
+    fn test_referenced_variables_follow_includes_and_parents() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_dir = temp_dir.path().to_path_buf();
+        fs::write(user_dir.join("colors.txt"), "{{ red }}").unwrap();
+        fs::write(
+            user_dir.join("base.txt"),
+            "{% block top %}{{ green }}{% endblock %}{% block bottom %}{{ blue }}{% endblock %}",
+        )
+        .unwrap();
+        fs::write(
+            user_dir.join("child.txt"),
+            "{% extends \"base.txt\" %}{% block top %}{% include \"colors.txt\" %}{% endblock %}\
+             {% block bottom %}{{ super() }}{{ cyan }}{% endblock %}",
+        )
+        .unwrap();
+
+        let renderer = TemplateRenderer::new(None)
+            .unwrap()
+            .with_user_dirs(std::slice::from_ref(&user_dir))
+            .reload()
+            .unwrap();
+
+        // The overridden `top` of the parent never renders
+        let vars = renderer.referenced_variables("child.txt").unwrap();
+        assert_eq!(vars.into_iter().collect::<Vec<_>>(), vec!["blue", "cyan", "red"]);
+
+        let strict = renderer.with_strict(true);
+        let err = strict.render("child.txt", &ColorPalette::default(), &HashMap::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("red"));
     }
 }
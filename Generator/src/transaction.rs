@@ -0,0 +1,317 @@
+use crate::atomic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tracing::{info, warn};
+
+/// What was at a path before the deploy touched it
+#[derive(Debug, Clone)]
+enum PriorState {
+    Missing,
+    File { content: Vec<u8>, mode: u32 },
+    Symlink(PathBuf),
+}
+
+impl PriorState {
+    fn summary(&self) -> Previous {
+        match self {
+            PriorState::Missing => Previous::Missing,
+            PriorState::File { .. } => Previous::File,
+            PriorState::Symlink(target) => Previous::Symlink(target.clone()),
+        }
+    }
+}
+
+/// What a changed path was before the deploy, without its content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Previous {
+    Missing,
+    File,
+    Symlink(PathBuf),
+}
+
+/// A file touched by a deploy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub previous: Previous,
+    /// Copy of the previous content in the backup directory, if one was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+    /// Permission bits of the previous file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+}
+
+static NEXT_JOURNAL: AtomicU64 = AtomicU64::new(0);
+
+/// Journal that most recently recorded each path
+static LATEST_JOURNAL: LazyLock<Mutex<HashMap<PathBuf, u64>>> = LazyLock::new(Default::default);
+
+/// Records the previous state of every path a program's deploy changes, so
+/// the changes can be undone if the deploy fails
+#[derive(Debug)]
+pub struct Journal {
+    id: u64,
+    entries: Mutex<Vec<(PathBuf, PriorState)>>,
+    backups: Mutex<Vec<(PathBuf, PathBuf)>>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Journal {
+            id: NEXT_JOURNAL.fetch_add(1, Ordering::SeqCst),
+            entries: Mutex::default(),
+            backups: Mutex::default(),
+        }
+    }
+}
+
+impl Journal {
+    /// Remember the current content of a file before it is written.
+    /// Only the first snapshot of a path counts. Writes go through a symlink,
+    /// so for one the file it points at is recorded and later restored.
+    pub fn record_file(&self, path: &Path) {
+        self.record(&resolve(path), false);
+    }
+
+    /// Remember what a path is before it is replaced by a symlink or removed
+    pub fn record_link(&self, path: &Path) {
+        self.record(path, true);
+    }
+
+    fn record(&self, path: &Path, as_link: bool) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.iter().any(|(p, _)| p == path) {
+            return;
+        }
+
+        let state = match fs::read_link(path) {
+            Ok(target) if as_link => PriorState::Symlink(target),
+            _ => match (fs::read(path), fs::metadata(path)) {
+                (Ok(content), Ok(metadata)) => PriorState::File {
+                    content,
+                    mode: metadata.permissions().mode() & 0o7777,
+                },
+                _ => PriorState::Missing,
+            },
+        };
+
+        entries.push((path.to_path_buf(), state));
+        LATEST_JOURNAL
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf(), self.id);
+    }
+
+    /// Remember where a file was backed up
+    /// The backup of a symlinked file goes with the file it points at, like
+    /// its snapshot; a snapshot of the link itself gets it too.
+    pub fn record_backup(&self, path: &Path, backup: &Path) {
+        let mut backups = self.backups.lock().unwrap_or_else(|e| e.into_inner());
+        let resolved = resolve(path);
+        if resolved != path {
+            backups.push((resolved, backup.to_path_buf()));
+        }
+        backups.push((path.to_path_buf(), backup.to_path_buf()));
+    }
+
+    /// Every path changed so far, with its backup
+    pub fn changes(&self) -> Vec<FileChange> {
+        let backups = self.backups.lock().unwrap_or_else(|e| e.into_inner());
+
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(path, state)| FileChange {
+                path: path.clone(),
+                previous: state.summary(),
+                backup: backups
+                    .iter()
+                    .rev()
+                    .find(|(p, _)| p == path)
+                    .map(|(_, backup)| backup.clone()),
+                mode: match state {
+                    PriorState::File { mode, .. } => Some(*mode),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+
+    /// Restore every recorded path, newest first
+    /// Keeps going after a failed restore and returns the first error.
+    pub fn rollback(&self) -> Result<Vec<PathBuf>> {
+        self.restore_where(|_| true)
+    }
+
+    /// Restore the recorded paths no later journal has recorded since
+    /// A later deploy's files are left alone instead of being reverted to
+    /// what was there before this one.
+    pub fn rollback_untouched(&self) -> Result<Vec<PathBuf>> {
+        let latest = LATEST_JOURNAL.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        self.restore_where(|path| {
+            let untouched = latest.get(path) == Some(&self.id);
+            if !untouched {
+                info!("Keeping {:?}, a later deploy changed it", path);
+            }
+            untouched
+        })
+    }
+
+    fn restore_where(&self, mut filter: impl FnMut(&Path) -> bool) -> Result<Vec<PathBuf>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut restored = Vec::new();
+        let mut first_error = None;
+
+        for (path, state) in entries.iter().rev().filter(|(path, _)| filter(path)) {
+            match restore(path, state) {
+                Ok(()) => {
+                    info!("↺ Restored {:?}", path);
+                    restored.push(path.clone());
+                }
+                Err(e) => {
+                    warn!("Failed to restore {:?}: {:#}", path, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(restored),
+        }
+    }
+}
+
+/// The file a symlink points at, or `path` itself
+fn resolve(path: &Path) -> PathBuf {
+    if path.is_symlink() {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn restore(path: &Path, state: &PriorState) -> Result<()> {
+    match state {
+        PriorState::Missing => match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {:?}", path))
+            }
+            _ => Ok(()),
+        },
+        PriorState::File { content, mode } => {
+            // A symlink put here by the deploy must not redirect the restore
+            if path.is_symlink() {
+                fs::remove_file(path)?;
+            }
+            atomic::write_atomic(path, content)?;
+            fs::set_permissions(path, fs::Permissions::from_mode(*mode))
+                .with_context(|| format!("Failed to restore permissions on {:?}", path))
+        }
+        PriorState::Symlink(target) => atomic::symlink_atomic(target, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rollback_restores_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let existing = temp_dir.path().join("config");
+        let created = temp_dir.path().join("new.css");
+        fs::write(&existing, "old").unwrap();
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let journal = Journal::default();
+        journal.record_file(&existing);
+        journal.record_file(&created);
+        fs::write(&existing, "new").unwrap();
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(&created, "new").unwrap();
+
+        // Later snapshots of the same path don't replace the original
+        journal.record_file(&existing);
+
+        assert_eq!(journal.rollback().unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert_eq!(fs::metadata(&existing).unwrap().permissions().mode() & 0o777, 0o755);
+        assert!(!created.exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let old_target = temp_dir.path().join("old.ini");
+        let new_target = temp_dir.path().join("new.ini");
+        let link = temp_dir.path().join("color.ini");
+        std::os::unix::fs::symlink(&old_target, &link).unwrap();
+
+        let journal = Journal::default();
+        journal.record_link(&link);
+        journal.record_backup(&link, &temp_dir.path().join("backup"));
+        atomic::symlink_atomic(&new_target, &link).unwrap();
+
+        let change = &journal.changes()[0];
+        assert_eq!(change.previous, Previous::Symlink(old_target.clone()));
+        assert_eq!(change.backup, Some(temp_dir.path().join("backup")));
+
+        journal.rollback().unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), old_target);
+    }
+
+    #[test]
+    fn test_rollback_through_user_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let dotfile = temp_dir.path().join("dotfiles/cava.conf");
+        let link = temp_dir.path().join("config");
+        fs::create_dir_all(dotfile.parent().unwrap()).unwrap();
+        fs::write(&dotfile, "old").unwrap();
+        std::os::unix::fs::symlink(&dotfile, &link).unwrap();
+
+        let journal = Journal::default();
+        journal.record_backup(&link, &temp_dir.path().join("backup"));
+        journal.record_file(&link);
+        atomic::write_atomic(&link, "new").unwrap();
+
+        let change = &journal.changes()[0];
+        assert_eq!(change.path, fs::canonicalize(&dotfile).unwrap());
+        assert_eq!(change.backup, Some(temp_dir.path().join("backup")));
+
+        journal.rollback().unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), dotfile);
+        assert_eq!(fs::read_to_string(&dotfile).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_rollback_untouched_keeps_later_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared.conf");
+        let own = temp_dir.path().join("own.conf");
+
+        let first = Journal::default();
+        first.record_file(&shared);
+        first.record_file(&own);
+        fs::write(&shared, "first").unwrap();
+        fs::write(&own, "first").unwrap();
+
+        let second = Journal::default();
+        second.record_file(&shared);
+        fs::write(&shared, "second").unwrap();
+
+        assert_eq!(first.rollback_untouched().unwrap(), vec![own.clone()]);
+        assert_eq!(fs::read_to_string(&shared).unwrap(), "second");
+        assert!(!own.exists());
+    }
+}
@@ -1,17 +1,208 @@
-use crate::generator::Generator;
+use crate::control::{self, ControlServer, ReportSummary, Status, WatchEvent};
+use crate::generator::{DeployReport, Generator};
+use crate::linker;
 use anyhow::{Context, Result};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::mpsc::channel;
-use std::time::Duration;
-use tokio::time::sleep;
-use tracing::{error, info, warn};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Requests to a running watcher, from signals or other tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Skip the programs the running deploy hasn't started, then stop
+    Shutdown,
+    /// Re-read the config file and redeploy the current theme
+    ReloadConfig,
+    /// Redeploy the current theme, including unchanged programs
+    Regenerate,
+    /// Hold file changes until `Resume`
+    Pause,
+    Resume,
+}
+
+/// Changes collected from a burst of events
+#[derive(Debug, Default, PartialEq)]
+struct Batch {
+    theme: bool,
+    config: bool,
+    template_paths: Vec<PathBuf>,
+}
+
+impl Batch {
+    fn is_empty(&self) -> bool {
+        !self.theme && !self.config && self.template_paths.is_empty()
+    }
+
+    fn merge(&mut self, other: Batch) {
+        self.theme |= other.theme;
+        self.config |= other.config;
+        for path in other.template_paths {
+            if !self.template_paths.contains(&path) {
+                self.template_paths.push(path);
+            }
+        }
+    }
+}
+
+/// Coalesces events until none has arrived for the quiet period
+struct Debouncer {
+    quiet: Duration,
+    batch: Batch,
+    last_event: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(quiet: Duration) -> Self {
+        Debouncer {
+            quiet,
+            batch: Batch::default(),
+            last_event: None,
+        }
+    }
+
+    fn theme_changed(&mut self, now: Instant) {
+        self.batch.theme = true;
+        self.last_event = Some(now);
+    }
+
+    fn config_changed(&mut self, now: Instant) {
+        self.batch.config = true;
+        self.last_event = Some(now);
+    }
+
+    fn templates_changed(&mut self, paths: Vec<PathBuf>, now: Instant) {
+        for path in paths {
+            if !self.batch.template_paths.contains(&path) {
+                self.batch.template_paths.push(path);
+            }
+        }
+        self.last_event = Some(now);
+    }
+
+    /// Time until the pending batch is due, `None` when nothing is pending
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.last_event
+            .map(|last| (last + self.quiet).saturating_duration_since(now))
+    }
+
+    /// The pending batch, once the quiet period has passed
+    fn take(&mut self, now: Instant) -> Option<Batch> {
+        if self.remaining(now)? > Duration::ZERO {
+            return None;
+        }
+
+        self.last_event = None;
+        Some(std::mem::take(&mut self.batch))
+    }
+}
+
+/// What the watcher is doing and has done, for `status` and the shutdown summary
+#[derive(Debug)]
+struct WatchState {
+    started: Instant,
+    paused: bool,
+    /// Deploys in progress
+    running: usize,
+    deploys: usize,
+    failed: usize,
+    superseded: usize,
+    last_report: Option<ReportSummary>,
+}
+
+impl WatchState {
+    fn new() -> Self {
+        WatchState {
+            started: Instant::now(),
+            paused: false,
+            running: 0,
+            deploys: 0,
+            failed: 0,
+            superseded: 0,
+            last_report: None,
+        }
+    }
+
+    fn record(&mut self, result: &Result<Option<DeployReport>>) {
+        match result {
+            Ok(Some(report)) => {
+                self.last_report = Some(ReportSummary::from(report));
+                self.deploys += 1;
+                if report.superseded {
+                    self.superseded += 1;
+                } else if report.failed() > 0 {
+                    self.failed += 1;
+                }
+            }
+            Ok(None) => {}
+            Err(_) => {
+                self.deploys += 1;
+                self.failed += 1;
+            }
+        }
+    }
+}
+
+fn lock(state: &Mutex<WatchState>) -> MutexGuard<'_, WatchState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Access to a running watcher from other tasks, such as the control socket
+#[derive(Clone)]
+pub struct WatchHandle {
+    watch_path: PathBuf,
+    state: Arc<Mutex<WatchState>>,
+    events: broadcast::Sender<WatchEvent>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl WatchHandle {
+    pub fn status(&self) -> Status {
+        let state = lock(&self.state);
+        Status {
+            pid: std::process::id(),
+            theme_dir: linker::resolve_link(&self.watch_path).ok(),
+            paused: state.paused,
+            deploying: state.running > 0,
+            uptime_secs: state.started.elapsed().as_secs(),
+            deploys: state.deploys,
+            failed: state.failed,
+            superseded: state.superseded,
+        }
+    }
+
+    /// Report of the last deploy that finished
+    pub fn last_report(&self) -> Option<ReportSummary> {
+        lock(&self.state).last_report.clone()
+    }
+
+    /// Queue a command for the watch loop
+    pub fn send(&self, command: Command) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| anyhow::anyhow!("The watcher has stopped"))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.events.subscribe()
+    }
+}
 
 /// Watch for theme changes and generate missing files
 pub struct ThemeWatcher {
     watch_path: PathBuf,
-    generator: Generator,
+    /// Replaced as a whole when the config is reloaded
+    generator: RwLock<Arc<Generator>>,
+    /// Config file reloaded when it changes and on `Command::ReloadConfig`
+    config_path: Option<PathBuf>,
+    state: Arc<Mutex<WatchState>>,
+    events: broadcast::Sender<WatchEvent>,
 }
 
 impl ThemeWatcher {
@@ -19,12 +210,70 @@ impl ThemeWatcher {
     pub fn new(watch_path: PathBuf, generator: Generator) -> Self {
         ThemeWatcher {
             watch_path,
-            generator,
+            generator: RwLock::new(Arc::new(generator)),
+            config_path: None,
+            state: Arc::new(Mutex::new(WatchState::new())),
+            events: broadcast::channel(64).0,
         }
     }
 
-    /// Start watching for theme changes
+    /// Config file to reload when it changes and on SIGHUP
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = Some(path);
+        self
+    }
+
+    /// Generator used for deployments
+    pub fn generator(&self) -> Arc<Generator> {
+        Arc::clone(&self.generator.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Handle for other tasks to query and command the watcher through `commands`
+    pub fn handle(&self, commands: mpsc::UnboundedSender<Command>) -> WatchHandle {
+        WatchHandle {
+            watch_path: self.watch_path.clone(),
+            state: Arc::clone(&self.state),
+            events: self.events.clone(),
+            commands,
+        }
+    }
+
+    /// Start watching for theme changes until SIGINT or SIGTERM
+    /// SIGHUP reloads the config and SIGUSR1 forces a regeneration. `ctl`
+    /// clients are answered on the control socket.
     pub async fn watch(&self) -> Result<()> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let signals = forward_signals(tx.clone())?;
+        let control = self.serve_control(tx).await;
+
+        let result = self.watch_with(rx).await;
+        signals.abort();
+        if let Some(control) = control {
+            // Dropping the server removes the socket
+            control.abort();
+            control.await.ok();
+        }
+        result
+    }
+
+    /// Answer `ctl` clients on a task, unless the socket can't be bound
+    async fn serve_control(&self, commands: mpsc::UnboundedSender<Command>) -> Option<JoinHandle<()>> {
+        let Some(path) = control::socket_path() else {
+            warn!("XDG_RUNTIME_DIR is not set, control socket disabled");
+            return None;
+        };
+
+        match ControlServer::bind(&path).await {
+            Ok(server) => Some(tokio::spawn(server.serve(self.handle(commands)))),
+            Err(e) => {
+                warn!("Control socket disabled: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Watch for theme changes, handling `commands` until `Command::Shutdown`
+    pub async fn watch_with(&self, mut commands: mpsc::UnboundedReceiver<Command>) -> Result<()> {
         info!("Starting theme watcher on {:?}", self.watch_path);
 
         // Create parent directory if it doesn't exist
@@ -39,16 +288,15 @@ impl ThemeWatcher {
         }
 
         // Run initial generation if symlink exists
+        let mut deploy = None;
         if self.watch_path.exists() {
-            if let Err(e) = self.handle_theme_change().await {
-                error!("Error during initial theme file generation: {}", e);
-            }
+            deploy = Some(self.spawn_theme_deploy(false, None));
         } else {
             warn!("Watch path doesn't exist yet: {:?}", self.watch_path);
         }
 
         // Set up file watcher
-        let (tx, rx) = channel();
+        let (tx, mut rx) = mpsc::unbounded_channel();
 
         let mut watcher = RecommendedWatcher::new(
             move |res: Result<Event, notify::Error>| {
@@ -75,31 +323,297 @@ impl ThemeWatcher {
 
         info!("Watching for changes in {:?}", watch_dir);
 
-        // Process events
-        loop {
-            match rx.recv() {
-                Ok(event) => {
-                    if self.is_relevant_event(&event) {
-                        info!("Theme change detected: {:?}", event.kind);
+        // Watch user template directories for hot-reload
+        self.watch_template_dirs(&mut watcher);
+
+        // Watch the config file's directory, as editors often replace the file
+        if let Some(config_dir) = self.config_path.as_deref().and_then(Path::parent) {
+            match watcher.watch(config_dir, RecursiveMode::NonRecursive) {
+                Ok(_) => info!("Watching config in {:?}", config_dir),
+                Err(e) => warn!("Failed to watch config directory {:?}: {}", config_dir, e),
+            }
+        }
 
-                        // Wait a bit for the symlink to settle
-                        sleep(Duration::from_millis(500)).await;
+        // Watch the current theme's color files for live preview
+        let mut theme_dir = None;
+        self.retarget(&mut watcher, &mut theme_dir);
 
-                        if let Err(e) = self.handle_theme_change().await {
-                            error!("Error handling theme change: {}", e);
+        // Process events, acting once a burst has gone quiet
+        let mut debouncer = Debouncer::new(self.debounce());
+        // Changes seen while paused
+        let mut held = Batch::default();
+        loop {
+            let wait = debouncer.remaining(Instant::now());
+
+            tokio::select! {
+                Some(command) = commands.recv() => {
+                    info!("Received {:?}", command);
+                    match command {
+                        Command::Shutdown => break,
+                        Command::ReloadConfig => {
+                            let batch = Batch {
+                                config: true,
+                                ..Batch::default()
+                            };
+                            self.handle_batch(batch, &mut watcher, &mut theme_dir, &mut debouncer, &mut deploy);
+                        }
+                        Command::Regenerate => {
+                            if deploy.is_some() {
+                                self.generator().cancel_deploys();
+                            }
+                            deploy = Some(self.spawn_theme_deploy(true, deploy.take()));
+                        }
+                        Command::Pause => {
+                            if !std::mem::replace(&mut lock(&self.state).paused, true) {
+                                info!("Paused, file changes are held until resumed");
+                                self.events.send(WatchEvent::Paused).ok();
+                            }
                         }
+                        Command::Resume => {
+                            if std::mem::replace(&mut lock(&self.state).paused, false) {
+                                info!("Resumed");
+                                self.events.send(WatchEvent::Resumed).ok();
+                                let batch = std::mem::take(&mut held);
+                                if !batch.is_empty() {
+                                    self.handle_batch(batch, &mut watcher, &mut theme_dir, &mut debouncer, &mut deploy);
+                                }
+                            }
+                        }
+                    }
+                }
+                event = rx.recv() => {
+                    let Some(event) = event else {
+                        error!("Watch error: event channel closed");
+                        break;
+                    };
+
+                    let template_paths = self.template_event_paths(&event);
+                    if self.is_config_event(&event) {
+                        debug!("Config change detected: {:?}", event.kind);
+                        debouncer.config_changed(Instant::now());
+                    } else if !template_paths.is_empty() {
+                        debug!("Template change detected: {:?}", template_paths);
+                        debouncer.templates_changed(template_paths, Instant::now());
+                    } else if self.is_relevant_event(&event) {
+                        debug!("Theme change detected: {:?}", event.kind);
+                        debouncer.theme_changed(Instant::now());
+                    } else if self.is_color_source_event(&event, theme_dir.as_deref()) {
+                        debug!("Theme colors edited: {:?}", event.paths);
+                        debouncer.theme_changed(Instant::now());
                     }
                 }
-                Err(e) => {
-                    error!("Watch error: {}", e);
-                    break;
+                _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                    let Some(batch) = debouncer.take(Instant::now()) else {
+                        continue;
+                    };
+
+                    if lock(&self.state).paused {
+                        held.merge(batch);
+                    } else {
+                        self.handle_batch(batch, &mut watcher, &mut theme_dir, &mut debouncer, &mut deploy);
+                    }
                 }
             }
         }
 
+        if let Some(running) = deploy.take() {
+            if !running.is_finished() {
+                info!("Waiting for the programs being deployed, skipping the rest");
+                self.generator().cancel_deploys();
+            }
+            running.await.ok();
+        }
+
+        let state = lock(&self.state);
+        info!(
+            "Theme watcher stopped after {}s: {} deploys, {} with failures, {} superseded",
+            state.started.elapsed().as_secs(),
+            state.deploys,
+            state.failed,
+            state.superseded
+        );
+
         Ok(())
     }
 
+    /// Act on a burst of changes: reload the config, then the templates, then
+    /// deploy what they affect
+    fn handle_batch(
+        &self,
+        batch: Batch,
+        watcher: &mut RecommendedWatcher,
+        theme_dir: &mut Option<PathBuf>,
+        debouncer: &mut Debouncer,
+        deploy: &mut Option<JoinHandle<()>>,
+    ) {
+        if batch.config {
+            match self.apply_config(watcher, debouncer) {
+                Some(changed) if changed.is_empty() => {
+                    info!("No enabled programs are affected by the config change");
+                }
+                Some(changed) if !batch.theme => {
+                    *deploy = Some(self.spawn_redeploy(changed, deploy.take()));
+                }
+                _ => {}
+            }
+        }
+
+        if !batch.template_paths.is_empty() {
+            // A theme switch in the same burst redeploys everything anyway
+            *deploy = Some(self.spawn_template_reload(batch.template_paths, !batch.theme, deploy.take()));
+        }
+
+        if batch.theme {
+            self.retarget(watcher, theme_dir);
+
+            // The latest theme wins: stop the running deploy before starting
+            if deploy.is_some() {
+                self.generator().cancel_deploys();
+            }
+            *deploy = Some(self.spawn_theme_deploy(false, deploy.take()));
+        }
+    }
+
+    /// Reload the config and apply its watch settings
+    /// Returns the programs whose settings changed, `None` if the config was rejected.
+    fn apply_config(&self, watcher: &mut RecommendedWatcher, debouncer: &mut Debouncer) -> Option<Vec<String>> {
+        match self.reload_config() {
+            Ok(changed) => {
+                self.watch_template_dirs(watcher);
+                debouncer.quiet = self.debounce();
+                self.events
+                    .send(WatchEvent::ConfigReloaded { changed: changed.clone() })
+                    .ok();
+                Some(changed)
+            }
+            Err(e) => {
+                error!("Invalid config, keeping the current one: {:#}", e);
+                self.events
+                    .send(WatchEvent::ConfigRejected { error: format!("{:#}", e) })
+                    .ok();
+                None
+            }
+        }
+    }
+
+    /// Quiet period that ends a burst of events
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(self.generator().config().debounce_ms)
+    }
+
+    /// Re-read and validate the config file, then replace the generator with one built from it
+    /// Returns the enabled programs whose settings changed. The current
+    /// generator stays when the file is missing or invalid.
+    fn reload_config(&self) -> Result<Vec<String>> {
+        let Some(path) = &self.config_path else {
+            anyhow::bail!("No config file to reload");
+        };
+        if !path.exists() {
+            anyhow::bail!("Config file {:?} was removed", path);
+        }
+
+        let config = crate::config::Config::load(path)?;
+        config.validate()?;
+
+        let old = self.generator();
+        if config.watch_path != old.config().watch_path {
+            warn!("Changing watch_path takes effect after a restart");
+        }
+        let changed = config.changed_programs(old.config());
+        let generator = old.with_config(config)?;
+
+        *self.generator.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(generator);
+        info!("✓ Reloaded config from {:?}", path);
+
+        Ok(changed)
+    }
+
+    /// Watch the user template directories for hot-reload
+    fn watch_template_dirs(&self, watcher: &mut RecommendedWatcher) {
+        for template_dir in &self.generator().config().template_dirs {
+            if let Err(e) = fs::create_dir_all(template_dir) {
+                warn!("Failed to create template directory {:?}: {}", template_dir, e);
+                continue;
+            }
+
+            match watcher.watch(template_dir, RecursiveMode::Recursive) {
+                Ok(_) => info!("Watching templates in {:?}", template_dir),
+                Err(e) => warn!("Failed to watch template directory {:?}: {}", template_dir, e),
+            }
+        }
+    }
+
+    /// Run `deploy` on a task once `previous` is done, logging and counting the outcome
+    fn spawn_deploy<F>(&self, previous: Option<JoinHandle<()>>, deploy: F) -> JoinHandle<()>
+    where
+        F: Future<Output = Result<Option<DeployReport>>> + Send + 'static,
+    {
+        let state = Arc::clone(&self.state);
+        let events = self.events.clone();
+
+        tokio::spawn(async move {
+            if let Some(previous) = previous {
+                previous.await.ok();
+            }
+
+            lock(&state).running += 1;
+            events.send(WatchEvent::DeployStarted).ok();
+            let result = deploy.await;
+            match &result {
+                Ok(Some(report)) if report.superseded => info!("Deploy of {:?} superseded", report.theme_dir),
+                Ok(_) => {}
+                Err(e) => error!("Deploy failed: {:#}", e),
+            }
+
+            let mut state = lock(&state);
+            state.running -= 1;
+            state.record(&result);
+            let event = match &result {
+                Ok(Some(_)) => state.last_report.clone().map(|report| WatchEvent::DeployFinished { report }),
+                Ok(None) => None,
+                Err(e) => Some(WatchEvent::DeployFailed { error: format!("{:#}", e) }),
+            };
+            if let Some(event) = event {
+                events.send(event).ok();
+            }
+        })
+    }
+
+    /// Redeploy `programs` for the current theme once `previous` is done,
+    /// even when their files are unchanged, as their settings changed
+    fn spawn_redeploy(&self, programs: Vec<String>, previous: Option<JoinHandle<()>>) -> JoinHandle<()> {
+        let generator = self.generator();
+        let watch_path = self.watch_path.clone();
+
+        self.spawn_deploy(previous, async move {
+            info!("Redeploying: {}", programs.join(", "));
+            let theme_dir = linker::resolve_link(&watch_path)?;
+            generator.regenerate(&theme_dir, Some(&programs)).await.map(Some)
+        })
+    }
+
+    /// Deploy the current theme once `previous` is done; `force` includes unchanged programs
+    fn spawn_theme_deploy(&self, force: bool, previous: Option<JoinHandle<()>>) -> JoinHandle<()> {
+        let generator = self.generator();
+        let watch_path = self.watch_path.clone();
+
+        self.spawn_deploy(previous, async move {
+            Self::deploy_current_theme(&generator, &watch_path, force).await
+        })
+    }
+
+    /// Check if an event touches the config file
+    fn is_config_event(&self, event: &Event) -> bool {
+        let Some(config_path) = &self.config_path else {
+            return false;
+        };
+
+        matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) && event.paths.iter().any(|path| path == config_path)
+    }
+
     /// Check if an event is relevant to our watch path
     fn is_relevant_event(&self, event: &Event) -> bool {
         // Check if any of the paths in the event match our watch path
@@ -117,53 +631,183 @@ impl ThemeWatcher {
         false
     }
 
-    /// Handle a theme change event
-    async fn handle_theme_change(&self) -> Result<()> {
-        // Read the symlink to get the target theme directory
-        let theme_dir = fs::read_link(&self.watch_path)
-            .with_context(|| format!("Failed to read symlink: {:?}", self.watch_path))?;
+    /// Check if an event touches a color source file of the watched theme directory
+    fn is_color_source_event(&self, event: &Event, theme_dir: Option<&Path>) -> bool {
+        let Some(theme_dir) = theme_dir else {
+            return false;
+        };
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return false;
+        }
+
+        event.paths.iter().any(|path| {
+            path.parent() == Some(theme_dir)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| self.generator().config().color_priority.iter().any(|source| source == name))
+        })
+    }
+
+    /// Move the theme directory watch to wherever `watch_path` points now
+    fn retarget(&self, watcher: &mut RecommendedWatcher, theme_dir: &mut Option<PathBuf>) {
+        let target = linker::resolve_link(&self.watch_path)
+            .ok()
+            .filter(|dir| dir.is_dir());
+        if target == *theme_dir {
+            return;
+        }
 
-        // If it's a relative path, make it absolute relative to the symlink's parent
-        let theme_dir = if theme_dir.is_relative() {
-            if let Some(parent) = self.watch_path.parent() {
-                parent.join(theme_dir)
-            } else {
-                theme_dir
+        if let Some(old) = theme_dir.take() {
+            if let Err(e) = watcher.unwatch(&old) {
+                debug!("Failed to unwatch theme directory {:?}: {}", old, e);
+            }
+        }
+
+        if let Some(dir) = target {
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(_) => {
+                    info!("Watching theme colors in {:?}", dir);
+                    *theme_dir = Some(dir);
+                }
+                Err(e) => warn!("Failed to watch theme directory {:?}: {}", dir, e),
+            }
+        }
+    }
+
+    /// Paths of a create/modify/remove event that lie inside a template directory
+    fn template_event_paths(&self, event: &Event) -> Vec<PathBuf> {
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return Vec::new();
+        }
+
+        let generator = self.generator();
+        let template_dirs = &generator.config().template_dirs;
+        event
+            .paths
+            .iter()
+            .filter(|path| template_dirs.iter().any(|dir| path.starts_with(dir)))
+            .cloned()
+            .collect()
+    }
+
+    /// Reload templates once `previous` is done and, with `redeploy`,
+    /// redeploy the programs that use the changed ones
+    fn spawn_template_reload(
+        &self,
+        paths: Vec<PathBuf>,
+        redeploy: bool,
+        previous: Option<JoinHandle<()>>,
+    ) -> JoinHandle<()> {
+        let generator = self.generator();
+        let watch_path = self.watch_path.clone();
+
+        self.spawn_deploy(previous, async move {
+            Self::handle_template_change(&generator, &watch_path, paths, redeploy).await
+        })
+    }
+
+    async fn handle_template_change(
+        generator: &Arc<Generator>,
+        watch_path: &Path,
+        paths: Vec<PathBuf>,
+        redeploy: bool,
+    ) -> Result<Option<DeployReport>> {
+        let reloader = Arc::clone(generator);
+        let reloaded = tokio::task::spawn_blocking(move || reloader.reload_templates(&paths)).await?;
+        let affected = match reloaded {
+            Ok(affected) => affected,
+            Err(e) => {
+                // Keep running with the previous templates
+                error!("Template error, keeping previous templates: {:#}", e);
+                return Ok(None);
             }
-        } else {
-            theme_dir
         };
 
+        if affected.is_empty() {
+            info!("No enabled programs use the changed templates");
+            return Ok(None);
+        }
+        if !redeploy {
+            return Ok(None);
+        }
+
+        info!("Redeploying: {}", affected.join(", "));
+        let theme_dir = linker::resolve_link(watch_path)?;
+        generator.redeploy_programs(&theme_dir, &affected).await.map(Some)
+    }
+
+    /// Deploy the theme `watch_path` points to
+    /// Returns `None` when the theme directory doesn't exist.
+    async fn deploy_current_theme(
+        generator: &Arc<Generator>,
+        watch_path: &Path,
+        force: bool,
+    ) -> Result<Option<DeployReport>> {
+        let theme_dir = linker::resolve_link(watch_path)?;
+
         info!("Theme changed to: {:?}", theme_dir);
 
         // Verify theme directory exists
         if !theme_dir.exists() {
             warn!("Theme directory doesn't exist: {:?}", theme_dir);
-            return Ok(());
+            return Ok(None);
         }
 
         // Use the new generate_and_deploy method for full workflow
-        self.generator.generate_and_deploy(&theme_dir)?;
-
-        Ok(())
+        if force {
+            generator.regenerate(&theme_dir, None).await.map(Some)
+        } else {
+            generator.generate_and_deploy(&theme_dir).await.map(Some)
+        }
     }
 
     /// Run once (generate files for current theme and exit)
-    pub async fn run_once(&self) -> Result<()> {
+    pub async fn run_once(&self) -> Result<Option<DeployReport>> {
         info!("Running once for current theme at {:?}", self.watch_path);
 
         if !self.watch_path.exists() {
             anyhow::bail!("Watch path doesn't exist: {:?}", self.watch_path);
         }
 
-        self.handle_theme_change().await
+        Self::deploy_current_theme(&self.generator(), &self.watch_path, false).await
     }
 }
 
+/// Forward SIGINT and SIGTERM as `Shutdown`, SIGHUP as `ReloadConfig` and
+/// SIGUSR1 as `Regenerate`
+fn forward_signals(commands: mpsc::UnboundedSender<Command>) -> Result<JoinHandle<()>> {
+    let mut interrupt = signal(SignalKind::interrupt()).context("Failed to handle SIGINT")?;
+    let mut terminate = signal(SignalKind::terminate()).context("Failed to handle SIGTERM")?;
+    let mut hangup = signal(SignalKind::hangup()).context("Failed to handle SIGHUP")?;
+    let mut user1 = signal(SignalKind::user_defined1()).context("Failed to handle SIGUSR1")?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            let command = tokio::select! {
+                _ = interrupt.recv() => Command::Shutdown,
+                _ = terminate.recv() => Command::Shutdown,
+                _ = hangup.recv() => Command::ReloadConfig,
+                _ = user1.recv() => Command::Regenerate,
+            };
+
+            if commands.send(command).is_err() {
+                break;
+            }
+        }
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Config;
+    use crate::config::{BackupSettings, Config, ProgramConfig};
     use std::fs;
     use tempfile::TempDir;
 
@@ -194,11 +838,235 @@ mod tests {
         #[cfg(unix)]
         std::os::unix::fs::symlink(&theme_dir, &symlink_path).unwrap();
 
-        let config = Config::default();
+        let config = Config {
+            generated_themes_dir: temp_dir.path().join("generated"),
+            deployments_dir: temp_dir.path().join("deployments"),
+            template_dirs: vec![temp_dir.path().join("templates")],
+            backups: BackupSettings {
+                dir: temp_dir.path().join("backups"),
+                ..BackupSettings::default()
+            },
+            ..Config::default()
+        };
         let generator = Generator::new(config).unwrap();
         let watcher = ThemeWatcher::new(symlink_path, generator);
 
         let result = watcher.run_once().await;
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_color_source_events() {
+        let generator = Generator::new(Config::default()).unwrap();
+        let watcher = ThemeWatcher::new(PathBuf::from("/t/current/theme"), generator);
+        let theme_dir = Path::new("/t/themes/tokyo-night");
+        let event = |path: &str| {
+            Event::new(EventKind::Modify(notify::event::ModifyKind::Any)).add_path(PathBuf::from(path))
+        };
+
+        let edit = event("/t/themes/tokyo-night/alacritty.toml");
+        assert!(watcher.is_color_source_event(&edit, Some(theme_dir)));
+        assert!(!watcher.is_color_source_event(&edit, None));
+        assert!(!watcher.is_color_source_event(&event("/t/themes/tokyo-night/backgrounds.png"), Some(theme_dir)));
+        assert!(!watcher.is_color_source_event(&event("/t/themes/other/alacritty.toml"), Some(theme_dir)));
+    }
+
+    /// A theme symlinked from `current` and a config deploying no programs
+    fn watch_fixture(temp_dir: &TempDir) -> (PathBuf, PathBuf, Config) {
+        let theme_dir = temp_dir.path().join("theme");
+        fs::create_dir(&theme_dir).unwrap();
+        fs::write(
+            theme_dir.join("alacritty.toml"),
+            "[colors.primary]\nbackground = \"#eff1f5\"\nforeground = \"#4c4f69\"\n",
+        )
+        .unwrap();
+
+        let symlink_path = temp_dir.path().join("current");
+        std::os::unix::fs::symlink(&theme_dir, &symlink_path).unwrap();
+
+        let config = Config {
+            watch_path: symlink_path.clone(),
+            generated_themes_dir: temp_dir.path().join("generated"),
+            deployments_dir: temp_dir.path().join("deployments"),
+            template_dirs: vec![temp_dir.path().join("templates")],
+            backups: BackupSettings {
+                dir: temp_dir.path().join("backups"),
+                ..BackupSettings::default()
+            },
+            programs: Vec::new(),
+            ..Config::default()
+        };
+        (theme_dir, symlink_path, config)
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_deploy() {
+        let temp_dir = TempDir::new().unwrap();
+        let (theme_dir, symlink_path, config) = watch_fixture(&temp_dir);
+        let config = Config {
+            pre_deploy: Some("sleep 0.5 && touch deployed".to_string()),
+            ..config
+        };
+        let watcher = ThemeWatcher::new(symlink_path, Generator::new(config).unwrap());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(Command::Shutdown).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), watcher.watch_with(rx)).await;
+
+        // Returns once the initial deploy has finished
+        assert!(result.unwrap().is_ok());
+        assert!(theme_dir.join("deployed").exists());
+        assert_eq!(lock(&watcher.state).deploys, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_redeploys() {
+        let temp_dir = TempDir::new().unwrap();
+        let (theme_dir, symlink_path, config) = watch_fixture(&temp_dir);
+        let program = |name: &str, post_deploy: &str| ProgramConfig {
+            name: name.to_string(),
+            kind: Some("file".to_string()),
+            enabled: true,
+            output_file: format!("{}.conf", name),
+            template: "omarcava".to_string(),
+            target: Some(temp_dir.path().join(name).display().to_string()),
+            post_deploy: Some(post_deploy.to_string()),
+            ..ProgramConfig::default()
+        };
+        let config = Config {
+            programs: vec![program("a", "true"), program("b", "true")],
+            ..config
+        };
+        let config_path = temp_dir.path().join("config.toml");
+        Config {
+            programs: vec![program("a", "touch reloaded"), program("b", "true")],
+            ..config.clone()
+        }
+        .save(&config_path)
+        .unwrap();
+
+        let watcher = ThemeWatcher::new(symlink_path, Generator::new(config).unwrap()).with_config_path(config_path);
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(Command::ReloadConfig).unwrap();
+        tx.send(Command::Shutdown).unwrap();
+        watcher.watch_with(rx).await.unwrap();
+
+        // Only the program whose settings changed is redeployed
+        assert!(theme_dir.join("reloaded").exists());
+        let state = lock(&watcher.state);
+        assert_eq!(state.deploys, 2);
+        let redeployed: Vec<&str> = state.last_report.as_ref().unwrap().programs.iter().map(|p| p.program.as_str()).collect();
+        assert_eq!(redeployed, vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_config_edits_are_hot_reloaded() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_, symlink_path, config) = watch_fixture(&temp_dir);
+        let config = Config { debounce_ms: 50, ..config };
+        let config_path = temp_dir.path().join("config.toml");
+        config.save(&config_path).unwrap();
+
+        let watcher = ThemeWatcher::new(symlink_path, Generator::new(config.clone()).unwrap())
+            .with_config_path(config_path.clone());
+        let (tx, rx) = mpsc::unbounded_channel();
+        let settle = || tokio::time::sleep(Duration::from_millis(500));
+
+        let edits = async {
+            settle().await;
+            Config { hook_timeout_secs: 7, ..config.clone() }.save(&config_path).unwrap();
+            settle().await;
+            assert_eq!(watcher.generator().config().hook_timeout_secs, 7);
+
+            // Invalid files keep the current config
+            fs::write(&config_path, "programs = 5").unwrap();
+            settle().await;
+            let unknown_source = Config {
+                color_priority: vec!["kitty.conf".to_string()],
+                ..config.clone()
+            };
+            unknown_source.save(&config_path).unwrap();
+            settle().await;
+            assert_eq!(watcher.generator().config().hook_timeout_secs, 7);
+            assert_eq!(watcher.generator().config().color_priority, config.color_priority);
+
+            tx.send(Command::Shutdown).unwrap();
+        };
+
+        let (result, _) = tokio::join!(watcher.watch_with(rx), edits);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_changes_until_resumed() {
+        let temp_dir = TempDir::new().unwrap();
+        let (theme_dir, symlink_path, config) = watch_fixture(&temp_dir);
+        let config = Config { debounce_ms: 50, ..config };
+        let watcher = ThemeWatcher::new(symlink_path, Generator::new(config).unwrap());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handle = watcher.handle(tx.clone());
+        let mut events = handle.subscribe();
+        let settle = || tokio::time::sleep(Duration::from_millis(500));
+
+        let commands = async {
+            settle().await;
+            tx.send(Command::Pause).unwrap();
+            settle().await;
+            assert!(handle.status().paused);
+            let deploys = handle.status().deploys;
+
+            fs::write(
+                theme_dir.join("alacritty.toml"),
+                "[colors.primary]\nbackground = \"#1e1e2e\"\nforeground = \"#cdd6f4\"\n",
+            )
+            .unwrap();
+            settle().await;
+            assert_eq!(handle.status().deploys, deploys);
+
+            tx.send(Command::Resume).unwrap();
+            settle().await;
+            assert_eq!(handle.status().deploys, deploys + 1);
+            tx.send(Command::Shutdown).unwrap();
+        };
+        let (result, _) = tokio::join!(watcher.watch_with(rx), commands);
+        assert!(result.is_ok());
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        // The held edit is deployed after resuming
+        let resumed = seen.iter().position(|e| *e == WatchEvent::Resumed).unwrap();
+        assert!(seen[..resumed].contains(&WatchEvent::Paused));
+        assert!(matches!(seen.last(), Some(WatchEvent::DeployFinished { .. })));
+        assert_eq!(handle.last_report().unwrap().theme_dir, theme_dir);
+    }
+
+    #[test]
+    fn test_debouncer_coalesces_bursts() {
+        let start = Instant::now();
+        let quiet = Duration::from_millis(300);
+        let mut debouncer = Debouncer::new(quiet);
+        assert_eq!(debouncer.remaining(start), None);
+
+        // Each event restarts the quiet period
+        debouncer.theme_changed(start);
+        debouncer.templates_changed(vec![PathBuf::from("/t/a.css")], start + Duration::from_millis(200));
+        debouncer.theme_changed(start + Duration::from_millis(400));
+        debouncer.templates_changed(vec![PathBuf::from("/t/a.css")], start + Duration::from_millis(400));
+        assert!(debouncer.take(start + Duration::from_millis(600)).is_none());
+        assert_eq!(debouncer.remaining(start + Duration::from_millis(600)), Some(Duration::from_millis(100)));
+
+        let batch = debouncer.take(start + Duration::from_millis(700)).unwrap();
+        assert_eq!(
+            batch,
+            Batch {
+                theme: true,
+                config: false,
+                template_paths: vec![PathBuf::from("/t/a.css")],
+            }
+        );
+        assert_eq!(debouncer.remaining(start + Duration::from_millis(700)), None);
+    }
 }
@@ -146,7 +146,7 @@ if command_exists spicetify; then
         # Copy text theme (base files: user.css, README, screenshots)
         cp -r "$TEXT_THEME_SOURCE" "$SPICETIFY_THEMES_DIR/"
 
-        # Remove the original color.ini (will be generated/symlinked by our generator)
+        # Remove the original color.ini (the generator symlinks its own, with all schemes)
         rm -f "$TEXT_THEME_DEST/color.ini"
 
         success "Text theme installed to $TEXT_THEME_DEST"
@@ -411,6 +411,7 @@ After=graphical-session.target
 [Service]
 Type=simple
 ExecStart=$INSTALL_DIR/$BINARY_NAME watch
+ExecReload=/bin/kill -HUP \$MAINPID
 Restart=on-failure
 RestartSec=10
 StandardOutput=journal